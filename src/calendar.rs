@@ -0,0 +1,242 @@
+/*
+    Reading days: the dates of a plan on which readings are placed.
+
+    Readings may be limited to chosen days of the week, written as a list of days and
+    ranges of days separated by `,`:
+        - a day:            "mon", "Monday", "sun"
+        - a range of days:  "mon-fri", "fri-mon" (wrapping past Sunday)
+        - a named set:      "weekdays", "weekends", "all"
+
+    Blackout dates, such as holidays or a retreat, are skipped whatever their day of the
+    week. They are written as a list of dates and inclusive date ranges separated by `,`:
+        "2025-12-25", "2025-12-24..2025-12-26"
+
+    The planner schedules the readings on consecutive days and then moves each day onto
+    the next reading day, so the readings still finish by the end of the plan.
+*/
+
+use chrono::{ Datelike, NaiveDate, Weekday };
+use crate::error::PlannerError;
+use crate::planner::CatchUp;
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun
+];
+
+// Parse a weekday selection such as "mon-fri" or "mon, wed, fri" into the selected days, in week order
+pub fn parse_weekdays(selection: &str) -> Result<Vec<Weekday>, PlannerError> {
+    let mut selected = [false; 7];
+
+    for item in selection.split(',').map(|item| item.trim()) {
+        match item.to_lowercase().as_str() {
+            "all" | "daily" => selected = [true; 7],
+            "weekdays" => selected[..5].fill(true),
+            "weekends" => selected[5..].fill(true),
+            _ => {
+                let (first, last) = match item.split_once('-') {
+                    Some((first, last)) => (parse_weekday(first)?, parse_weekday(last)?),
+                    None => {
+                        let day = parse_weekday(item)?;
+                        (day, day)
+                    }
+                };
+                // Walk forward from the first day so that a range may wrap past Sunday
+                let mut day = first;
+                loop {
+                    selected[day.num_days_from_monday() as usize] = true;
+                    if day == last {
+                        break;
+                    }
+                    day = day.succ();
+                }
+            }
+        }
+    }
+
+    Ok(WEEKDAYS.iter().filter(|day| selected[day.num_days_from_monday() as usize]).copied().collect())
+}
+
+fn parse_weekday(name: &str) -> Result<Weekday, PlannerError> {
+    name.trim().parse::<Weekday>()
+        .map_err(|_| PlannerError::InvalidWeekdays(format!("unknown day of the week '{}'", name.trim())))
+}
+
+// Parse blackout dates such as "2025-12-25, 2026-04-03..2026-04-06" into inclusive date ranges
+pub fn parse_blackouts(selection: &str) -> Result<Vec<(NaiveDate, NaiveDate)>, PlannerError> {
+    selection.split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let (first, last) = match item.split_once("..") {
+                Some((first, last)) => (parse_date(first)?, parse_date(last)?),
+                None => {
+                    let date = parse_date(item)?;
+                    (date, date)
+                }
+            };
+            if last < first {
+                return Err(PlannerError::InvalidBlackout(format!("blackout range {} ends before it starts", item)));
+            }
+            Ok((first, last))
+        })
+        .collect()
+}
+
+fn parse_date(text: &str) -> Result<NaiveDate, PlannerError> {
+    NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d")
+        .map_err(|_| PlannerError::InvalidBlackout(format!("invalid blackout date '{}', expected YYYY-MM-DD", text.trim())))
+}
+
+// Find the reading days of a plan from `start`, either up to and including `end` or, given a day
+// count, the first `days` of them. An empty weekday list reads every day. Dates within a blackout
+// range are never reading days, and the walk steps over each range at once
+pub(crate) fn get_reading_dates(
+    start: NaiveDate,
+    end: Option<NaiveDate>,
+    days: Option<i32>,
+    weekdays: &[Weekday],
+    blackouts: &[(NaiveDate, NaiveDate)]) -> Vec<NaiveDate>
+{
+    let (count, end) = match (days, end) {
+        (Some(days), _) => (days.max(0) as usize, NaiveDate::MAX),
+        (None, Some(end)) => (usize::MAX, end),
+        (None, None) => return Vec::new()
+    };
+    // Each week outside the blackouts has a reading day on every selected weekday, so a day count
+    // is reached within this many dates outside the blackouts
+    let most_dates = count.div_ceil(reading_weekdays(weekdays)).saturating_mul(7);
+
+    let mut dates: Vec<NaiveDate> = Vec::new();
+    let mut date = start;
+    let mut walked: usize = 0;
+    while dates.len() < count && date <= end && walked < most_dates {
+        let blackout_end = blackouts.iter()
+            .filter(|(first, last)| *first <= date && date <= *last)
+            .map(|(_, last)| *last)
+            .max();
+        let last = match blackout_end {
+            Some(last) => last,
+            None => {
+                if weekdays.is_empty() || weekdays.contains(&date.weekday()) {
+                    dates.push(date);
+                }
+                walked += 1;
+                date
+            }
+        };
+        match last.succ_opt() {
+            Some(next) => date = next,
+            None => break
+        }
+    }
+    dates
+}
+
+// The number of different weekdays read on, all seven for an empty list
+fn reading_weekdays(weekdays: &[Weekday]) -> usize {
+    let mut selected: Vec<Weekday> = weekdays.to_vec();
+    selected.sort_by_key(|weekday| weekday.num_days_from_monday());
+    selected.dedup();
+    if selected.is_empty() { 7 } else { selected.len() }
+}
+
+// The most catch-up days a policy can mark in a plan of `days` reading days and `books` books,
+// found without listing the dates, so that a day count too large for the chapters is rejected
+// before the dates are searched for
+pub(crate) fn most_catch_up_days(
+    catch_up: CatchUp,
+    days: i32,
+    books: usize,
+    weekdays: &[Weekday],
+    blackouts: &[(NaiveDate, NaiveDate)]) -> i64
+{
+    let days = days as i64;
+    // The most dates the reading days can span, counting every blackout in full
+    let blackout_days: i64 = blackouts.iter().map(|(first, last)| (*last - *first).num_days() + 1).sum();
+    let weeks = (days + reading_weekdays(weekdays) as i64 - 1) / reading_weekdays(weekdays) as i64;
+    let span = weeks * 7 + blackout_days;
+    let most = match catch_up {
+        CatchUp::Auto => 1,
+        CatchUp::None => 0,
+        CatchUp::Every(n) => days / n.max(1) as i64,
+        CatchUp::Weekly(_) => span / 7 + 1,
+        CatchUp::MonthEnd => span / 28 + 1,
+        CatchUp::Count(count) => count as i64,
+        CatchUp::BookEnd | CatchUp::TestamentEnd => books as i64 - 1
+    };
+    most.clamp(0, days)
+}
+
+// Mark the reading days that an explicit catch-up policy turns into catch-up days. Boundary
+// policies depend on the readings, so they mark none here
+pub(crate) fn get_catch_up_slots(catch_up: CatchUp, dates: &[NaiveDate]) -> Vec<bool> {
+    let total = dates.len();
+    if let CatchUp::Count(count) = catch_up {
+        // Spread evenly so that the last catch-up day is the final day. More catch-up days than
+        // days makes every day one
+        let count = (count as usize).min(total);
+        let mut slots: Vec<bool> = vec![false; total];
+        for j in 1..=count {
+            slots[(j * total).div_ceil(count) - 1] = true;
+        }
+        return slots;
+    }
+
+    dates.iter()
+        .enumerate()
+        .map(|(i, date)| match catch_up {
+            CatchUp::Every(n) => (i + 1) % n as usize == 0,
+            CatchUp::Weekly(weekday) => date.weekday() == weekday,
+            // The last reading day of each month, and the final day if it ends its month
+            CatchUp::MonthEnd => match dates.get(i + 1) {
+                Some(next) => next.month() != date.month(),
+                None => date.succ_opt().is_some_and(|next| next.month() != date.month())
+            },
+            CatchUp::Auto | CatchUp::None | CatchUp::BookEnd | CatchUp::TestamentEnd | CatchUp::Count(_) => false
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dates(first: (i32, u32, u32), days: i64) -> Vec<NaiveDate> {
+        let first = NaiveDate::from_ymd_opt(first.0, first.1, first.2).unwrap();
+        first.iter_days().take(days as usize).collect()
+    }
+
+    fn positions(slots: &[bool]) -> Vec<usize> {
+        slots.iter().enumerate().filter(|(_, &is_catch_up)| is_catch_up).map(|(i, _)| i).collect()
+    }
+
+    #[test]
+    fn catch_up_slots_follow_the_policy() {
+        let january = dates((2025, 1, 1), 31);
+        assert_eq!(positions(&get_catch_up_slots(CatchUp::Every(7), &january)), vec![6, 13, 20, 27]);
+        // 5 January 2025 is a Sunday
+        assert_eq!(positions(&get_catch_up_slots(CatchUp::Weekly(Weekday::Sun), &january)), vec![4, 11, 18, 25]);
+        assert_eq!(positions(&get_catch_up_slots(CatchUp::MonthEnd, &january)), vec![30]);
+        assert_eq!(positions(&get_catch_up_slots(CatchUp::Count(3), &dates((2025, 1, 1), 10))), vec![3, 6, 9]);
+        for catch_up in [CatchUp::Auto, CatchUp::None, CatchUp::BookEnd, CatchUp::TestamentEnd] {
+            assert!(positions(&get_catch_up_slots(catch_up, &january)).is_empty());
+        }
+    }
+
+    #[test]
+    fn month_end_catch_up_is_the_last_reading_day_of_each_month() {
+        // Reading on weekdays only, 31 May 2025 is a Saturday, so May's last reading day is the 30th
+        let weekdays: Vec<NaiveDate> = dates((2025, 5, 26), 14).into_iter()
+            .filter(|date| date.weekday().num_days_from_monday() < 5)
+            .collect();
+        let slots = get_catch_up_slots(CatchUp::MonthEnd, &weekdays);
+        let catch_ups: Vec<NaiveDate> = positions(&slots).into_iter().map(|i| weekdays[i]).collect();
+        assert_eq!(catch_ups, vec![NaiveDate::from_ymd_opt(2025, 5, 30).unwrap()]);
+    }
+
+    #[test]
+    fn catch_up_count_larger_than_the_days_makes_every_day_one() {
+        let slots = get_catch_up_slots(CatchUp::Count(4_000_000_000), &dates((2025, 1, 1), 5));
+        assert_eq!(slots, vec![true; 5]);
+    }
+}
@@ -0,0 +1,327 @@
+use std::path::PathBuf;
+use chrono::NaiveDate;
+use clap::{ Args, Parser, Subcommand };
+use clap::builder::{ PossibleValuesParser, TypedValueParser };
+use bible_planner::{ Algorithm, Balance, CatchUp, CatchUpSync, Format, Grouping, Layout };
+use bible_planner::config::{ PlanConfig, PRESET_NAMES };
+
+#[derive(Debug, Parser)]
+#[command(name = "bible_planner", version, about = "Daily Bible reading planner")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+
+    /// Read chapter data from a CSV file (index,title,chapter,length) instead of the built-in data
+    #[arg(long, global = true, value_name = "FILE")]
+    pub data: Option<PathBuf>,
+
+    /// Read verse lengths from a CSV file (index,title,chapter,verse,length) for --split-chapters
+    #[arg(long, global = true, value_name = "FILE")]
+    pub verse_data: Option<PathBuf>
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Generate a reading plan and write it to a file
+    Generate(GenerateArgs),
+    /// List the books of the Bible with their indexes and chapter counts
+    ListBooks,
+    /// Show chapter counts and daily reading lengths for a plan without writing it
+    Stats(PlanArgs),
+    /// Record days of a saved plan as read
+    Mark(MarkArgs),
+    /// Show each reader's progress through a saved plan
+    Status(StatusArgs),
+    /// Spread the chapters not yet read in a saved plan over the days left
+    Replan(ReplanArgs)
+}
+
+#[derive(Debug, Args)]
+pub struct GenerateArgs {
+    #[command(flatten)]
+    pub plan: PlanArgs,
+
+    /// Output file path [default: reading_plan_<timestamp>]
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Output format: "text", "ics" (an iCalendar file with one all-day event per day), "csv"
+    /// (one row per reading per track), "json", an "html" or "markdown" checklist, or "latex"
+    /// [default: from the output file extension, otherwise text]
+    #[arg(short, long, value_parser = PossibleValuesParser::new(["text", "ics", "csv", "json", "html", "markdown", "latex"]).try_map(|s| s.parse::<Format>()))]
+    pub format: Option<Format>,
+
+    /// Name of the plan, used as the checklist title and the calendar name. Keep it the same when
+    /// re-importing an updated plan so that its events are replaced [default: Bible Reading Plan]
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Text shown under the title of an HTML, Markdown or LaTeX checklist
+    #[arg(long, value_name = "TEXT")]
+    pub header: Option<String>,
+
+    /// Group the days of an HTML, Markdown or LaTeX checklist by "month" or "week" [default: month]
+    #[arg(long, value_parser = PossibleValuesParser::new(["month", "week"]).try_map(|s| s.parse::<Grouping>()))]
+    pub group_by: Option<Grouping>,
+
+    /// Page layout of a LaTeX plan: a two-column A5 "booklet" or a narrow "bookmark" strip [default: booklet]
+    #[arg(long, value_parser = PossibleValuesParser::new(["booklet", "bookmark"]).try_map(|s| s.parse::<Layout>()))]
+    pub layout: Option<Layout>,
+
+    /// Print the days skipped by --weekdays or --blackout as rest days rather than leaving them out
+    #[arg(long)]
+    pub rest_days: bool,
+
+    /// Include the daily reading length (character count) in the printout
+    #[arg(short = 'l', long)]
+    pub show_length: bool,
+
+    /// Include the estimated daily reading and listening times, with weekly and total summaries
+    #[arg(short = 'm', long)]
+    pub show_time: bool,
+
+    /// Characters heard per minute of an audio Bible, for listening times [default: 850]
+    #[arg(long, value_name = "CHARS", value_parser = clap::value_parser!(i32).range(1..))]
+    pub listening_speed: Option<i32>,
+
+    /// Also save the plan to a progress state file for the mark and status commands
+    #[arg(long, value_name = "FILE")]
+    pub state: Option<PathBuf>
+}
+
+#[derive(Debug, Args)]
+pub struct MarkArgs {
+    /// Progress state file saved with generate --state
+    pub state: PathBuf,
+
+    /// Days to mark, e.g. "5", "1-7", "2025-06-21", "2025-06-21..2025-06-27" or "today",
+    /// separated by ',' [default: the first reading day not yet read]
+    pub days: Option<String>,
+
+    /// Name of the reader [default: default]
+    #[arg(short, long)]
+    pub reader: Option<String>,
+
+    /// Mark the days as not read
+    #[arg(long)]
+    pub undo: bool,
+
+    /// Date to take as today [default: today]
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    pub today: Option<NaiveDate>
+}
+
+#[derive(Debug, Args)]
+pub struct StatusArgs {
+    /// Progress state file saved with generate --state
+    pub state: PathBuf,
+
+    /// Show only this reader [default: every reader]
+    #[arg(short, long)]
+    pub reader: Option<String>,
+
+    /// Date to measure progress on [default: today]
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    pub today: Option<NaiveDate>
+}
+
+#[derive(Debug, Args)]
+pub struct ReplanArgs {
+    /// Progress state file saved with generate --state, updated with the new plan
+    pub state: PathBuf,
+
+    /// Last day read, as a day number or a date, or 0 to replan from the first day [default: the day before the reader's first day not yet read]
+    #[arg(long, value_name = "DAY")]
+    pub last: Option<String>,
+
+    /// Name of the reader whose progress sets the last day read [default: default]
+    #[arg(short, long)]
+    pub reader: Option<String>,
+
+    /// First day of the new plan [default: today]
+    #[arg(short, long, value_name = "YYYY-MM-DD")]
+    pub start: Option<NaiveDate>,
+
+    /// Last day of the new plan [default: the end of the saved plan]
+    #[arg(short, long, value_name = "YYYY-MM-DD")]
+    pub end: Option<NaiveDate>,
+
+    /// How to split each book's chapters across its days: "heuristic" or "optimal" [default: heuristic]
+    #[arg(short, long, value_parser = PossibleValuesParser::new(["heuristic", "optimal"]).try_map(|s| s.parse::<Algorithm>()))]
+    pub algorithm: Option<Algorithm>,
+
+    /// How to allocate days: "book" or "track" [default: book]
+    #[arg(short, long, value_parser = PossibleValuesParser::new(["book", "track"]).try_map(|s| s.parse::<Balance>()))]
+    pub balance: Option<Balance>,
+
+    /// Where to put catch-up days, as for generate [default: auto]
+    #[arg(short = 'u', long, value_name = "POLICY", value_parser = |s: &str| s.parse::<CatchUp>())]
+    pub catch_up: Option<CatchUp>,
+
+    /// Even out the daily length of all tracks together, as for generate
+    #[arg(long)]
+    pub combined_balance: bool,
+
+    /// Whether the tracks share their catch-up days, as for generate [default: independent]
+    #[arg(long, value_name = "SYNC", value_parser = PossibleValuesParser::new(["independent", "joint"]).try_map(|s| s.parse::<CatchUpSync>()))]
+    pub catch_up_sync: Option<CatchUpSync>,
+
+    /// Days of the week to read on, e.g. "mon-fri" [default: every day]
+    #[arg(short, long, value_name = "DAYS")]
+    pub weekdays: Option<String>,
+
+    /// Dates to skip, e.g. "2025-12-25" or "2025-12-24..2025-12-26"
+    #[arg(short = 'x', long = "blackout", value_name = "DATES")]
+    pub blackouts: Vec<String>,
+
+    /// Also write the new days to this file, in the format given by --format or the file extension
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Output format, as for generate [default: from the output file extension, otherwise text]
+    #[arg(short, long, value_parser = PossibleValuesParser::new(["text", "ics", "csv", "json", "html", "markdown", "latex"]).try_map(|s| s.parse::<Format>()))]
+    pub format: Option<Format>,
+
+    /// Include the daily reading length (character count) in the printout
+    #[arg(short = 'l', long)]
+    pub show_length: bool,
+
+    /// Include the estimated daily reading and listening times, with weekly and total summaries
+    #[arg(short = 'm', long)]
+    pub show_time: bool
+}
+
+#[derive(Debug, Args)]
+pub struct PlanArgs {
+    /// Start from a built-in preset, overriding its settings with any other options
+    #[arg(short, long, value_parser = PossibleValuesParser::new(PRESET_NAMES))]
+    pub preset: Option<String>,
+
+    /// Load plan settings from a TOML or YAML file, overriding the preset
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Books to read, e.g. "NT", "Matthew-John", "Gen, Ex" or "Psalms-Proverbs x2", or "Chronological"
+    /// for every chapter in the order of events. Separate tracks read in parallel with ';' or repeat
+    /// the option [default: Bible]
+    #[arg(short, long = "track", value_name = "BOOKS")]
+    pub tracks: Vec<String>,
+
+    /// First day of the reading [default: today]
+    #[arg(short, long, value_name = "YYYY-MM-DD")]
+    pub start: Option<NaiveDate>,
+
+    /// Last day of the reading
+    #[arg(short, long, value_name = "YYYY-MM-DD")]
+    pub end: Option<NaiveDate>,
+
+    /// Total number of days, used instead of an end date. Days are numbered rather than
+    /// dated unless a start date is given
+    #[arg(short, long, conflicts_with = "end")]
+    pub days: Option<i32>,
+
+    /// Aim for about this many characters a day across all tracks, used instead of an end date.
+    /// The number of days is worked out from the chapter lengths
+    #[arg(long, value_name = "CHARS", conflicts_with_all = ["end", "days"])]
+    pub daily_length: Option<i32>,
+
+    /// Aim for about this many minutes of reading a day at the --reading-speed, used instead of an end date
+    #[arg(long, value_name = "MINUTES", conflicts_with_all = ["end", "days", "daily_length"])]
+    pub daily_minutes: Option<i32>,
+
+    /// Split chapters longer than this fraction of a track's average daily length at verse
+    /// boundaries, e.g. 0.75. Requires --verse-data
+    #[arg(long, value_name = "FRACTION")]
+    pub split_chapters: Option<f64>,
+
+    /// Characters read per minute, for --daily-minutes and reading times [default: 1200]
+    #[arg(long, value_name = "CHARS", value_parser = clap::value_parser!(i32).range(1..))]
+    pub reading_speed: Option<i32>,
+
+    /// How to split each book's chapters across its days: "heuristic" searches for a threshold
+    /// on each day's length, "optimal" minimizes the variance of the daily lengths without making
+    /// any day longer than the heuristic's longest [default: heuristic]
+    #[arg(short, long, value_parser = PossibleValuesParser::new(["heuristic", "optimal"]).try_map(|s| s.parse::<Algorithm>()))]
+    pub algorithm: Option<Algorithm>,
+
+    /// How to allocate days: "book" gives each book a whole number of days, "track" splits the
+    /// whole track as one stream of chapters for an even daily length across books [default: book]
+    #[arg(short, long, value_parser = PossibleValuesParser::new(["book", "track"]).try_map(|s| s.parse::<Balance>()))]
+    pub balance: Option<Balance>,
+
+    /// With --balance track, don't let a day read the end of one book and the start of the next
+    #[arg(long)]
+    pub no_span_books: bool,
+
+    /// With several tracks, even out the daily length of all tracks together, moving chapters
+    /// between the days of a book in one track to make up for long or short days in the others
+    #[arg(long)]
+    pub combined_balance: bool,
+
+    /// Where to put catch-up days: "auto" fills the days left over after planning; "none", "every:N"
+    /// (every Nth day), a day of the week such as "sunday", "book" or "testament" (after the day
+    /// that ends a book or testament), "month-end" or "count:N" (N days spread evenly) place exactly
+    /// those catch-up days [default: auto]
+    #[arg(short = 'u', long, value_name = "POLICY", value_parser = |s: &str| s.parse::<CatchUp>())]
+    pub catch_up: Option<CatchUp>,
+
+    /// With several tracks, "independent" gives each track its own catch-up days; "joint" makes all
+    /// tracks catch up on the same days and spreads each track over the others [default: independent]
+    #[arg(long, value_name = "SYNC", value_parser = PossibleValuesParser::new(["independent", "joint"]).try_map(|s| s.parse::<CatchUpSync>()))]
+    pub catch_up_sync: Option<CatchUpSync>,
+
+    /// Days of the week to read on, e.g. "mon-fri", "mon,wed,fri" or "weekends". With --days,
+    /// the count is of reading days [default: every day]
+    #[arg(short, long, value_name = "DAYS")]
+    pub weekdays: Option<String>,
+
+    /// Dates to skip, e.g. "2025-12-25" or "2025-12-24..2025-12-26". Separate several with ','
+    /// or repeat the option. The readings are moved onto the remaining days
+    #[arg(short = 'x', long = "blackout", value_name = "DATES")]
+    pub blackouts: Vec<String>
+}
+
+impl PlanArgs {
+    // The plan settings set on the command line
+    pub fn to_config(&self) -> PlanConfig {
+        PlanConfig {
+            tracks: if self.tracks.is_empty() { None } else { Some(self.tracks.clone()) },
+            start: self.start,
+            end: self.end,
+            days: self.days,
+            daily_length: self.daily_length,
+            daily_minutes: self.daily_minutes,
+            reading_speed: self.reading_speed,
+            split_chapters: self.split_chapters,
+            algorithm: self.algorithm,
+            balance: self.balance,
+            span_books: if self.no_span_books { Some(false) } else { None },
+            combined_balance: if self.combined_balance { Some(true) } else { None },
+            catch_up: self.catch_up,
+            catch_up_sync: self.catch_up_sync,
+            weekdays: self.weekdays.clone(),
+            blackouts: if self.blackouts.is_empty() { None } else { Some(self.blackouts.clone()) },
+            ..PlanConfig::default()
+        }
+    }
+}
+
+impl GenerateArgs {
+    // The plan and output settings set on the command line
+    pub fn to_config(&self) -> PlanConfig {
+        PlanConfig {
+            output: self.output.clone(),
+            format: self.format,
+            title: self.title.clone(),
+            header: self.header.clone(),
+            group_by: self.group_by,
+            layout: self.layout,
+            rest_days: if self.rest_days { Some(true) } else { None },
+            show_length: if self.show_length { Some(true) } else { None },
+            show_time: if self.show_time { Some(true) } else { None },
+            listening_speed: self.listening_speed,
+            state: self.state.clone(),
+            ..self.plan.to_config()
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::fs;
+use std::path::{ Path, PathBuf };
+use chrono::NaiveDate;
+use serde::{ Deserialize, Deserializer };
+use crate::error::PlannerError;
+use crate::output::{ Format, Grouping, Layout };
+use crate::planner::{ Algorithm, Balance, CatchUp, CatchUpSync };
+
+/*
+    Plan settings loaded from a TOML or YAML file, a built-in preset or the command line.
+    Every field is optional so that settings can be layered: a preset, then a config file,
+    then command-line options, each overriding the fields set by the one before. For example:
+
+        tracks = ["NT", "Psalms-Proverbs x2"]
+        start = 2025-06-21
+        end = 2025-09-21
+        algorithm = "optimal"
+        show_length = true
+*/
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlanConfig {
+    pub tracks: Option<Vec<String>>,
+    #[serde(deserialize_with = "deserialize_date")]
+    pub start: Option<NaiveDate>,
+    #[serde(deserialize_with = "deserialize_date")]
+    pub end: Option<NaiveDate>,
+    pub days: Option<i32>,
+    pub daily_length: Option<i32>,
+    pub daily_minutes: Option<i32>,
+    pub algorithm: Option<Algorithm>,
+    pub balance: Option<Balance>,
+    pub span_books: Option<bool>,
+    pub combined_balance: Option<bool>,
+    pub catch_up: Option<CatchUp>,
+    pub catch_up_sync: Option<CatchUpSync>,
+    pub weekdays: Option<String>,
+    pub blackouts: Option<Vec<String>>,
+    pub output: Option<PathBuf>,
+    pub format: Option<Format>,
+    pub title: Option<String>,
+    pub header: Option<String>,
+    pub group_by: Option<Grouping>,
+    pub layout: Option<Layout>,
+    pub rest_days: Option<bool>,
+    pub show_length: Option<bool>,
+    pub show_time: Option<bool>,
+    pub reading_speed: Option<i32>,
+    pub split_chapters: Option<f64>,
+    pub listening_speed: Option<i32>,
+    pub state: Option<PathBuf>
+}
+
+// Names of the built-in presets, in the order they are listed in the help text
+pub const PRESET_NAMES: [&str; 4] = [
+    "bible-in-a-year",
+    "nt-in-90-days",
+    "psalms-proverbs-monthly",
+    "nt-psalms-proverbs"
+];
+
+impl PlanConfig {
+    // Override the fields of this config with those set in `other`. An end date, a day count, a daily
+    // length and a daily reading time are alternatives, so setting any one clears the others
+    pub fn merge(mut self, other: PlanConfig) -> PlanConfig {
+        if other.tracks.is_some() {
+            self.tracks = other.tracks;
+        }
+        if other.start.is_some() {
+            self.start = other.start;
+        }
+        if other.end.is_some() || other.days.is_some() || other.daily_length.is_some() || other.daily_minutes.is_some() {
+            self.end = other.end;
+            self.days = other.days;
+            self.daily_length = other.daily_length;
+            self.daily_minutes = other.daily_minutes;
+        }
+        if other.algorithm.is_some() {
+            self.algorithm = other.algorithm;
+        }
+        if other.balance.is_some() {
+            self.balance = other.balance;
+        }
+        if other.span_books.is_some() {
+            self.span_books = other.span_books;
+        }
+        if other.combined_balance.is_some() {
+            self.combined_balance = other.combined_balance;
+        }
+        if other.catch_up.is_some() {
+            self.catch_up = other.catch_up;
+        }
+        if other.catch_up_sync.is_some() {
+            self.catch_up_sync = other.catch_up_sync;
+        }
+        if other.weekdays.is_some() {
+            self.weekdays = other.weekdays;
+        }
+        if other.blackouts.is_some() {
+            self.blackouts = other.blackouts;
+        }
+        if other.output.is_some() {
+            self.output = other.output;
+        }
+        if other.format.is_some() {
+            self.format = other.format;
+        }
+        if other.title.is_some() {
+            self.title = other.title;
+        }
+        if other.header.is_some() {
+            self.header = other.header;
+        }
+        if other.group_by.is_some() {
+            self.group_by = other.group_by;
+        }
+        if other.layout.is_some() {
+            self.layout = other.layout;
+        }
+        if other.rest_days.is_some() {
+            self.rest_days = other.rest_days;
+        }
+        if other.show_length.is_some() {
+            self.show_length = other.show_length;
+        }
+        if other.show_time.is_some() {
+            self.show_time = other.show_time;
+        }
+        if other.reading_speed.is_some() {
+            self.reading_speed = other.reading_speed;
+        }
+        if other.split_chapters.is_some() {
+            self.split_chapters = other.split_chapters;
+        }
+        if other.listening_speed.is_some() {
+            self.listening_speed = other.listening_speed;
+        }
+        if other.state.is_some() {
+            self.state = other.state;
+        }
+        self
+    }
+}
+
+// Load a plan config, choosing the format from the file extension
+pub fn load_config(path: &Path) -> Result<PlanConfig, PlannerError> {
+    let config_error = |message: String| PlannerError::Config { path: path.to_path_buf(), message };
+    let contents = fs::read_to_string(path).map_err(|e| config_error(e.to_string()))?;
+
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "toml" => toml::from_str(&contents).map_err(|e| config_error(e.to_string())),
+        "yaml" | "yml" => serde_yaml::from_str(&contents).map_err(|e| config_error(e.to_string())),
+        _ => Err(config_error("expected a .toml, .yaml or .yml extension".to_string()))
+    }
+}
+
+// Get a built-in preset by name
+pub fn preset(name: &str) -> Option<PlanConfig> {
+    let (tracks, days): (&[&str], i32) = match name {
+        "bible-in-a-year" => (&["Bible"], 365),
+        "nt-in-90-days" => (&["NT"], 90),
+        "psalms-proverbs-monthly" => (&["Psalms-Proverbs"], 30),
+        "nt-psalms-proverbs" => (&["NT", "Psalms-Proverbs x2"], 92),
+        _ => return None
+    };
+
+    Some(PlanConfig {
+        tracks: Some(tracks.iter().map(|t| t.to_string()).collect()),
+        days: Some(days),
+        ..PlanConfig::default()
+    })
+}
+
+// Accept dates written either as strings or, in TOML, as bare local dates
+fn deserialize_date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDate>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum DateValue {
+        Text(String),
+        Toml(toml::value::Datetime)
+    }
+
+    let text = match Option::<DateValue>::deserialize(deserializer)? {
+        None => return Ok(None),
+        Some(DateValue::Text(text)) => text,
+        Some(DateValue::Toml(datetime)) => datetime.to_string()
+    };
+    NaiveDate::parse_from_str(&text, "%Y-%m-%d")
+        .map(Some)
+        .map_err(|_| serde::de::Error::custom(format!("invalid date '{}', expected YYYY-MM-DD", text)))
+}
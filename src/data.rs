@@ -0,0 +1,227 @@
+use std::fs::File;
+use std::io::{ ErrorKind, Read };
+use std::path::Path;
+use std::collections::HashMap;
+use csv::ReaderBuilder;
+use serde::Deserialize;
+use crate::error::PlannerError;
+
+// The default chapter data, compiled into the binary so it runs from any directory
+pub const BIBLE_CSV: &str = include_str!("bible.csv");
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ChapterData {
+    pub title: String,
+    pub chapters: i32,
+    pub length: i32
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct IndexData {
+    pub index: i32,
+    pub title: String,
+    pub chapter: i32,
+    pub length: i32
+}
+
+#[derive(Debug, Clone)]
+pub struct BookData {
+    pub index: i32,
+    pub title: String,
+    // The length of each chapter, with chapter n at position n - 1
+    pub chapter_lengths: Vec<i32>
+}
+
+impl BookData {
+    pub fn chapters(&self) -> i32 {
+        self.chapter_lengths.len() as i32
+    }
+
+    pub fn length(&self) -> i32 {
+        self.chapter_lengths.iter().sum()
+    }
+}
+
+// The chapter data, loaded once and indexed by book index and title
+#[derive(Debug, Clone)]
+pub struct BibleData {
+    // Books ordered by index
+    books: Vec<BookData>,
+    positions_by_index: HashMap<i32, usize>,
+    positions_by_title: HashMap<String, usize>
+}
+
+impl BibleData {
+    // Load the chapter data from `file_path` if given, otherwise from the built-in data
+    pub fn load(file_path: Option<&Path>) -> Result<BibleData, PlannerError> {
+        match file_path {
+            Some(file_path) => {
+                let file = File::open(file_path).map_err(|e| match e.kind() {
+                    ErrorKind::NotFound => PlannerError::DataFileMissing(file_path.to_path_buf()),
+                    _ => PlannerError::Io(e)
+                })?;
+                BibleData::from_reader(file)
+            }
+            None => BibleData::from_reader(BIBLE_CSV.as_bytes())
+        }
+    }
+
+    // Read CSV records of index, title, chapter and length. The chapters of each book must
+    // be listed in order starting from 1
+    pub fn from_reader(reader: impl Read) -> Result<BibleData, PlannerError> {
+        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(reader);
+        let mut books: Vec<BookData> = Vec::new();
+        let mut positions_by_index: HashMap<i32, usize> = HashMap::new();
+
+        let headers = rdr.headers().map_err(malformed_csv)?.clone();
+
+        for result in rdr.records() {
+            let row = result.map_err(malformed_csv)?;
+            let line = row.position().map(|p| p.line()).unwrap_or(0);
+            let record: IndexData = row.deserialize(Some(&headers)).map_err(malformed_csv)?;
+
+            let position = *positions_by_index.entry(record.index).or_insert_with(|| {
+                books.push(BookData { index: record.index, title: record.title.clone(), chapter_lengths: Vec::new() });
+                books.len() - 1
+            });
+            let book = &mut books[position];
+            if book.title != record.title {
+                return Err(PlannerError::MalformedCsv {
+                    line,
+                    message: format!("index {} is used for both {} and {}", record.index, book.title, record.title)
+                });
+            }
+            if record.chapter != book.chapters() + 1 {
+                return Err(PlannerError::MalformedCsv {
+                    line,
+                    message: format!("{} chapter {} is out of order, expected chapter {}", record.title, record.chapter, book.chapters() + 1)
+                });
+            }
+            book.chapter_lengths.push(record.length);
+        }
+
+        Ok(BibleData::from_books(books))
+    }
+
+    // Index books whose chapters are already loaded, each with a distinct index and title
+    pub(crate) fn from_books(mut books: Vec<BookData>) -> BibleData {
+        books.sort_by_key(|book| book.index);
+        let positions_by_index = books.iter().enumerate().map(|(i, book)| (book.index, i)).collect();
+        let positions_by_title = books.iter().enumerate().map(|(i, book)| (book.title.clone(), i)).collect();
+
+        BibleData { books, positions_by_index, positions_by_title }
+    }
+
+    pub fn books(&self) -> &[BookData] {
+        &self.books
+    }
+
+    pub fn titles(&self) -> Vec<String> {
+        self.books.iter().map(|book| book.title.clone()).collect()
+    }
+
+    pub fn book(&self, index: i32) -> Option<&BookData> {
+        self.positions_by_index.get(&index).map(|&i| &self.books[i])
+    }
+
+    pub fn book_by_title(&self, title: &str) -> Option<&BookData> {
+        self.positions_by_title.get(title).map(|&i| &self.books[i])
+    }
+
+    pub fn chapter_length(&self, title: &str, chapter: i32) -> Option<i32> {
+        let book = self.book_by_title(title)?;
+        if chapter < 1 {
+            return None;
+        }
+        book.chapter_lengths.get(chapter as usize - 1).copied()
+    }
+
+    // Create a vector with title, number of chapters, total length for each of the selected books
+    pub fn get_bible_data(&self, book_index: &[i32]) -> Result<Vec<ChapterData>, PlannerError> {
+        book_index.iter()
+            .map(|&index| {
+                let book = self.book(index)
+                    .ok_or_else(|| PlannerError::InvalidSelection(format!("book index {} is not in the chapter data", index)))?;
+                Ok(ChapterData { title: book.title.clone(), chapters: book.chapters(), length: book.length() })
+            })
+            .collect()
+    }
+
+    // Create a vector with title, chapter number and length for each chapter of a book
+    pub fn get_chapter_data(&self, title: &str) -> Vec<ChapterData> {
+        self.book_by_title(title)
+            .map(|book| book.chapter_lengths.iter()
+                .enumerate()
+                .map(|(i, &length)| ChapterData { title: book.title.clone(), chapters: i as i32 + 1, length })
+                .collect())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct VerseRecord {
+    pub index: i32,
+    pub title: String,
+    pub chapter: i32,
+    pub verse: i32,
+    pub length: i32
+}
+
+// Verse lengths for splitting long chapters, loaded from a CSV file of index, title, chapter,
+// verse and length. Only the chapters that may be split need to be listed
+#[derive(Debug, Clone, Default)]
+pub struct VerseData {
+    // The length of each verse by book index and chapter, with verse n at position n - 1
+    verse_lengths: HashMap<(i32, i32), Vec<i32>>
+}
+
+impl VerseData {
+    pub fn load(file_path: &Path) -> Result<VerseData, PlannerError> {
+        let file = File::open(file_path).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => PlannerError::DataFileMissing(file_path.to_path_buf()),
+            _ => PlannerError::Io(e)
+        })?;
+        VerseData::from_reader(file)
+    }
+
+    // Read CSV records of index, title, chapter, verse and length. The verses of each chapter must
+    // be listed in order starting from 1
+    pub fn from_reader(reader: impl Read) -> Result<VerseData, PlannerError> {
+        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(reader);
+        let mut verse_lengths: HashMap<(i32, i32), Vec<i32>> = HashMap::new();
+
+        let headers = rdr.headers().map_err(malformed_csv)?.clone();
+
+        for result in rdr.records() {
+            let row = result.map_err(malformed_csv)?;
+            let line = row.position().map(|p| p.line()).unwrap_or(0);
+            let record: VerseRecord = row.deserialize(Some(&headers)).map_err(malformed_csv)?;
+
+            let verses = verse_lengths.entry((record.index, record.chapter)).or_default();
+            if record.verse != verses.len() as i32 + 1 {
+                return Err(PlannerError::MalformedCsv {
+                    line,
+                    message: format!("{} {}:{} is out of order, expected verse {}", record.title, record.chapter, record.verse, verses.len() + 1)
+                });
+            }
+            verses.push(record.length);
+        }
+
+        Ok(VerseData { verse_lengths })
+    }
+
+    // The length of each verse of a chapter, if it is listed
+    pub fn verse_lengths(&self, index: i32, chapter: i32) -> Option<&[i32]> {
+        self.verse_lengths.get(&(index, chapter)).map(|verses| verses.as_slice())
+    }
+}
+
+// Convert a CSV error into an error with the line number of the bad record
+pub(crate) fn malformed_csv(e: csv::Error) -> PlannerError {
+    let line = e.position().map(|p| p.line()).unwrap_or(0);
+    let message = match e.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => err.to_string(),
+        _ => e.to_string()
+    };
+    PlannerError::MalformedCsv { line, message }
+}
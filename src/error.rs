@@ -0,0 +1,148 @@
+use std::fmt;
+use std::io;
+use std::error::Error;
+use std::path::PathBuf;
+use chrono::NaiveDate;
+
+// Errors returned while selecting books, loading data, planning and writing a reading plan
+#[derive(Debug)]
+pub enum PlannerError {
+    // No tracks or books were selected
+    NoBooksSelected,
+    // A book name matched no title, with the closest titles as suggestions
+    UnknownBook { name: String, suggestions: Vec<String> },
+    // A book name is a prefix of several titles
+    AmbiguousBook { name: String, matches: Vec<String> },
+    // A book selection could not be parsed
+    InvalidSelection(String),
+    // The end date is not after the start date
+    InvalidDates { start: NaiveDate, end: NaiveDate },
+    // The number of days is not positive
+    InvalidDuration(i32),
+    // The target daily length is not positive
+    InvalidDailyLength(i32),
+    // The fraction of the daily length above which chapters are split is not positive
+    InvalidSplitFraction(f64),
+    // A catch-up policy's interval or count is zero
+    InvalidCatchUp(String),
+    // Neither an end date, a number of days nor a daily length was given
+    MissingEndDate,
+    // A weekday selection could not be parsed
+    InvalidWeekdays(String),
+    // A blackout date or range could not be parsed
+    InvalidBlackout(String),
+    // No reading days fall between the start and end dates, or fewer than the number of days asked for
+    NoReadingDays { start: NaiveDate, end: NaiveDate },
+    // There are more days than chapters to read
+    TooManyDays { days: i32, chapters: i32 },
+    // A book was assigned more days than it has chapters
+    TooFewChapters { title: String, chapters: i32, days: i32 },
+    // The readings run past the last day of the plan
+    DateOverflow { date: NaiveDate, end: NaiveDate },
+    // The chapter data file does not exist
+    DataFileMissing(PathBuf),
+    // The chapter data file has a bad record
+    MalformedCsv { line: u64, message: String },
+    // A config file could not be read or parsed
+    Config { path: PathBuf, message: String },
+    // A progress state file could not be read or parsed, or a day given for it is not in the plan
+    State { path: PathBuf, message: String },
+    // A saved plan cannot be planned again from the given day and dates
+    Replan(String),
+    // Reading or writing a file failed
+    Io(io::Error)
+}
+
+impl PlannerError {
+    // Process exit code for the CLI. Exit code 2 is left for command-line usage errors
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PlannerError::NoBooksSelected
+            | PlannerError::UnknownBook { .. }
+            | PlannerError::AmbiguousBook { .. }
+            | PlannerError::InvalidSelection(_) => 3,
+            PlannerError::InvalidDates { .. }
+            | PlannerError::InvalidDuration(_)
+            | PlannerError::InvalidDailyLength(_)
+            | PlannerError::InvalidSplitFraction(_)
+            | PlannerError::InvalidCatchUp(_)
+            | PlannerError::MissingEndDate
+            | PlannerError::InvalidWeekdays(_)
+            | PlannerError::InvalidBlackout(_)
+            | PlannerError::NoReadingDays { .. }
+            | PlannerError::TooManyDays { .. }
+            | PlannerError::TooFewChapters { .. }
+            | PlannerError::DateOverflow { .. } => 4,
+            PlannerError::DataFileMissing(_)
+            | PlannerError::MalformedCsv { .. } => 5,
+            PlannerError::Config { .. } => 6,
+            PlannerError::Io(_) => 7,
+            PlannerError::State { .. }
+            | PlannerError::Replan(_) => 8
+        }
+    }
+}
+
+impl fmt::Display for PlannerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlannerError::NoBooksSelected => write!(f, "no books selected"),
+            PlannerError::UnknownBook { name, suggestions } => {
+                if suggestions.is_empty() {
+                    write!(f, "unknown book '{}'", name)
+                } else {
+                    write!(f, "unknown book '{}', did you mean {}?", name, suggestions.join(", "))
+                }
+            }
+            PlannerError::AmbiguousBook { name, matches } =>
+                write!(f, "'{}' is ambiguous, did you mean {}?", name, matches.join(", ")),
+            PlannerError::InvalidSelection(message) => write!(f, "{}", message),
+            PlannerError::InvalidDates { start, end } =>
+                write!(f, "end date {} must be after start date {}", end, start),
+            PlannerError::InvalidDuration(days) =>
+                write!(f, "the number of days must be greater than zero: {}", days),
+            PlannerError::InvalidDailyLength(length) =>
+                write!(f, "the daily length must be greater than zero: {}", length),
+            PlannerError::InvalidSplitFraction(fraction) =>
+                write!(f, "the fraction of the daily length for splitting chapters must be greater than zero: {}", fraction),
+            PlannerError::InvalidCatchUp(message) => write!(f, "{}", message),
+            PlannerError::MissingEndDate => write!(f, "an end date, a number of days or a daily length is required"),
+            PlannerError::InvalidWeekdays(message) => write!(f, "{}", message),
+            PlannerError::InvalidBlackout(message) => write!(f, "{}", message),
+            PlannerError::NoReadingDays { start, end } =>
+                write!(f, "there are not enough reading days between {} and {}", start, end),
+            PlannerError::TooManyDays { days, chapters } =>
+                write!(f, "the number of days may not exceed the number of chapters: {} > {}", days, chapters),
+            PlannerError::TooFewChapters { title, chapters, days } =>
+                write!(f, "the number of chapters in {} is less than the number of days assigned: {} < {}. \
+                    Add more chapters or reduce the number of days", title, chapters, days),
+            PlannerError::DateOverflow { date, end } =>
+                write!(f, "reading dates go past the last designated date: {} > {}", date, end),
+            PlannerError::DataFileMissing(path) =>
+                write!(f, "chapter data file {} not found", path.display()),
+            PlannerError::MalformedCsv { line, message } =>
+                write!(f, "malformed chapter data on line {}: {}", line, message),
+            PlannerError::Config { path, message } =>
+                write!(f, "invalid config file {}: {}", path.display(), message),
+            PlannerError::State { path, message } =>
+                write!(f, "progress state file {}: {}", path.display(), message),
+            PlannerError::Replan(message) => write!(f, "cannot replan: {}", message),
+            PlannerError::Io(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl Error for PlannerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PlannerError::Io(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for PlannerError {
+    fn from(e: io::Error) -> PlannerError {
+        PlannerError::Io(e)
+    }
+}
@@ -0,0 +1,23 @@
+/*
+    BiblePlanner library: plan a daily Bible reading with a consistent length of
+    reading per day, based on the length of the individual chapters.
+    Build a PlanRequest, generate a Plan and write it out with write_to_file.
+*/
+
+pub mod calendar;
+pub mod config;
+pub mod data;
+pub mod error;
+pub mod output;
+pub mod passage;
+pub mod plan;
+mod planner;
+pub mod progress;
+pub mod selection;
+
+pub use data::{ BibleData, ChapterData, VerseData };
+pub use error::PlannerError;
+pub use passage::{ Passage, chronological_order };
+pub use output::{ Format, Grouping, Layout, OutputOptions, write_plan, write_to_file };
+pub use plan::{ DEFAULT_LISTENING_SPEED, DEFAULT_READING_SPEED, Plan, PlanRequest, Reading, ScheduleDay, TrackDay };
+pub use planner::{ Algorithm, Balance, CatchUp, CatchUpSync, ChaptersDate, DailyLength };
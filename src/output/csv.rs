@@ -0,0 +1,93 @@
+use std::path::Path;
+use chrono::NaiveDate;
+use csv::Writer;
+use serde::Serialize;
+use crate::plan::{ Plan, TrackDay };
+use super::{ OutputOptions, output_days };
+
+// One row of the CSV output: a reading of one track, a catch-up day or a rest day
+#[derive(Debug, Serialize)]
+struct CsvRow {
+    date: Option<NaiveDate>,
+    day: Option<i32>,
+    track: Option<usize>,
+    book: String,
+    start_chapter: Option<i32>,
+    end_chapter: Option<i32>,
+    start_verse: Option<i32>,
+    end_verse: Option<i32>,
+    length: i32,
+    reading_minutes: i32,
+    listening_minutes: i32,
+    catch_up: bool,
+    rest_day: bool
+}
+
+// Write the plan as CSV with a header and one row per reading per track, with its estimated reading
+// and listening minutes. A catch-up or rest day is a single row with no track or book, and a rest
+// day has no day number. A track catching up on a day another track reads has a catch-up row with
+// its track number but no book. Option: duration_flag: leave the dates empty
+pub fn write_csv(filename: &Path, plan: &Plan, options: &OutputOptions) -> std::io::Result<()> {
+    let mut writer = Writer::from_path(filename)?;
+
+    for day in output_days(plan, options) {
+        let date = if options.duration_flag { None } else { Some(day.date) };
+
+        if day.readings.is_empty() {
+            writer.serialize(CsvRow {
+                date,
+                day: day.number,
+                track: None,
+                book: String::new(),
+                start_chapter: None,
+                end_chapter: None,
+                start_verse: None,
+                end_verse: None,
+                length: 0,
+                reading_minutes: 0,
+                listening_minutes: 0,
+                catch_up: day.is_catch_up_day(),
+                rest_day: day.is_rest_day()
+            })?;
+        }
+        for (i, track_day) in day.tracks.iter().enumerate() {
+            // A track catching up while another reads gets a catch-up row of its own
+            if *track_day == TrackDay::CatchUp && !day.readings.is_empty() {
+                writer.serialize(CsvRow {
+                    date,
+                    day: day.number,
+                    track: Some(i + 1),
+                    book: String::new(),
+                    start_chapter: None,
+                    end_chapter: None,
+                    start_verse: None,
+                    end_verse: None,
+                    length: 0,
+                    reading_minutes: 0,
+                    listening_minutes: 0,
+                    catch_up: true,
+                    rest_day: false
+                })?;
+            }
+            for reading in day.readings.iter().filter(|r| r.track == i + 1) {
+                writer.serialize(CsvRow {
+                    date,
+                    day: day.number,
+                    track: Some(reading.track),
+                    book: reading.titles.join(", "),
+                    start_chapter: Some(reading.start_chapter),
+                    end_chapter: Some(reading.end_chapter),
+                    start_verse: reading.start_verse,
+                    end_verse: reading.end_verse,
+                    length: reading.length,
+                    reading_minutes: options.reading_minutes(reading.length),
+                    listening_minutes: options.listening_minutes(reading.length),
+                    catch_up: false,
+                    rest_day: false
+                })?;
+            }
+        }
+    }
+
+    writer.flush()
+}
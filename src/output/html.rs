@@ -0,0 +1,116 @@
+use std::fs::File;
+use std::io::{ BufWriter, Write };
+use std::path::Path;
+use crate::plan::Plan;
+use super::{ OutputOptions, Summary, day_label, format_minutes, group_days, total_summary, track_cells, track_headings, week_summaries };
+
+// Print-friendly styling: catch-up days are shaded, rest days greyed out and each group stays on one page where it fits
+const STYLE: &str = "\
+body { font-family: Georgia, serif; margin: 2em; }
+h1 { margin-bottom: 0.2em; }
+.header { margin-top: 0; color: #444; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1.5em; page-break-inside: avoid; }
+th, td { border-bottom: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }
+td.check { width: 1.5em; }
+td.length, td.time { text-align: right; color: #666; }
+tr.total td { border-top: 2px solid #000; font-weight: bold; }
+tr.catch-up td { background: #f3f0e0; font-style: italic; }
+tr.rest td { color: #999; }
+@media print { body { margin: 0; } input { -webkit-appearance: none; appearance: none; border: 1px solid #000; width: 0.9em; height: 0.9em; } }";
+
+// Write the plan as a standalone HTML checklist with a checkbox per day, one table per month or
+// week and a column per track. Option: time_flag: add a reading time column and a table of weekly
+// and total reading and listening times
+pub fn write_html(filename: &Path, plan: &Plan, options: &OutputOptions) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(filename)?);
+    let tracks = plan.tracks.len();
+
+    writeln!(file, "<!DOCTYPE html>")?;
+    writeln!(file, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(file, "<title>{}</title>", escape_html(&options.title))?;
+    writeln!(file, "<style>\n{}\n</style>\n</head>\n<body>", STYLE)?;
+    writeln!(file, "<h1>{}</h1>", escape_html(&options.title))?;
+    if let Some(header) = &options.header {
+        writeln!(file, "<p class=\"header\">{}</p>", escape_html(header))?;
+    }
+
+    for group in group_days(plan, options) {
+        writeln!(file, "<h2>{}</h2>\n<table>", escape_html(&group.heading))?;
+
+        write!(file, "<tr><th></th><th>Day</th>")?;
+        for heading in track_headings(tracks) {
+            write!(file, "<th>{}</th>", heading)?;
+        }
+        if options.length_flag {
+            write!(file, "<th>Length</th>")?;
+        }
+        if options.time_flag {
+            write!(file, "<th>Time</th>")?;
+        }
+        writeln!(file, "</tr>")?;
+
+        for day in &group.days {
+            if day.is_rest_day() {
+                write!(file, "<tr class=\"rest\"><td class=\"check\"></td><td>{}</td>", day_label(day, options.duration_flag))?;
+                write!(file, "<td colspan=\"{}\">Rest day</td>", tracks)?;
+                if options.length_flag {
+                    write!(file, "<td></td>")?;
+                }
+                if options.time_flag {
+                    write!(file, "<td></td>")?;
+                }
+                writeln!(file, "</tr>")?;
+                continue;
+            }
+            if day.readings.is_empty() {
+                write!(file, "<tr class=\"catch-up\"><td class=\"check\"><input type=\"checkbox\"></td><td>{}</td>", day_label(day, options.duration_flag))?;
+                write!(file, "<td colspan=\"{}\">Catch-up day</td>", tracks)?;
+            } else {
+                write!(file, "<tr><td class=\"check\"><input type=\"checkbox\"></td><td>{}</td>", day_label(day, options.duration_flag))?;
+                for cell in track_cells(day) {
+                    write!(file, "<td>{}</td>", escape_html(&cell))?;
+                }
+            }
+            if options.length_flag {
+                write!(file, "<td class=\"length\">{}</td>", day.length)?;
+            }
+            if options.time_flag {
+                write!(file, "<td class=\"time\">{}</td>", if day.readings.is_empty() { String::new() } else { format_minutes(options.reading_minutes(day.length)) })?;
+            }
+            writeln!(file, "</tr>")?;
+        }
+
+        writeln!(file, "</table>")?;
+    }
+
+    if options.time_flag {
+        writeln!(file, "<h2>Weekly summary</h2>\n<table>")?;
+        writeln!(file, "<tr><th>Week</th><th>Reading days</th><th>Reading</th><th>Listening</th></tr>")?;
+        for summary in week_summaries(plan, options) {
+            write_summary_row(&mut file, &summary, "")?;
+        }
+        write_summary_row(&mut file, &total_summary(plan, options), " class=\"total\"")?;
+        writeln!(file, "</table>")?;
+    }
+
+    writeln!(file, "</body>\n</html>")?;
+    file.flush()
+}
+
+fn write_summary_row(file: &mut impl Write, summary: &Summary, class: &str) -> std::io::Result<()> {
+    writeln!(file, "<tr{}><td>{}</td><td>{}</td><td class=\"time\">{}</td><td class=\"time\">{}</td></tr>",
+        class,
+        escape_html(&summary.heading),
+        summary.reading_days,
+        format_minutes(summary.reading_minutes),
+        format_minutes(summary.listening_minutes)
+    )
+}
+
+// Escape the characters that have a special meaning in HTML text
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,89 @@
+use std::fs::File;
+use std::io::{ BufWriter, Write };
+use std::path::Path;
+use chrono::{ Duration, Utc };
+use crate::plan::Plan;
+use super::{ OutputOptions, format_minutes, output_days, track_cells };
+
+/*
+    Write the plan as an iCalendar (RFC 5545) file with one all-day event per day. The summary
+    holds the day's readings, with "Catch-up" for a track that catches up while another reads, and
+    the description its estimated reading and listening times and its length. Catch-up days are summarized as "Catch-up day" and put in a "Catch-up" category;
+    rest days, if included, are summarized as "Rest day" in a "Rest" category.
+
+    Each event's UID is made from its date and the plan title, not from its contents, so
+    importing an updated plan with the same title replaces the earlier events.
+*/
+pub fn write_ics(filename: &Path, plan: &Plan, options: &OutputOptions) -> std::io::Result<()> {
+    let title = &options.title;
+    let mut file = BufWriter::new(File::create(filename)?);
+    let timestamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let uid_suffix = slug(title);
+
+    write_line(&mut file, "BEGIN:VCALENDAR")?;
+    write_line(&mut file, "VERSION:2.0")?;
+    write_line(&mut file, "PRODID:-//BiblePlanner//Reading Plan//EN")?;
+    write_line(&mut file, "CALSCALE:GREGORIAN")?;
+    write_line(&mut file, &format!("X-WR-CALNAME:{}", escape_text(title)))?;
+
+    for day in output_days(plan, &OutputOptions { duration_flag: false, ..options.clone() }) {
+        let date = day.date;
+
+        write_line(&mut file, "BEGIN:VEVENT")?;
+        write_line(&mut file, &format!("UID:{}-{}@bible-planner", date.format("%Y%m%d"), uid_suffix))?;
+        write_line(&mut file, &format!("DTSTAMP:{}", timestamp))?;
+        write_line(&mut file, &format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")))?;
+        write_line(&mut file, &format!("DTEND;VALUE=DATE:{}", (date + Duration::days(1)).format("%Y%m%d")))?;
+        if day.is_rest_day() {
+            write_line(&mut file, "SUMMARY:Rest day")?;
+            write_line(&mut file, "CATEGORIES:Rest")?;
+        } else if day.is_catch_up_day() {
+            write_line(&mut file, "SUMMARY:Catch-up day")?;
+            write_line(&mut file, "CATEGORIES:Catch-up")?;
+        } else {
+            let summary = track_cells(&day).into_iter().filter(|cell| !cell.is_empty()).collect::<Vec<String>>().join(", ");
+            write_line(&mut file, &format!("SUMMARY:{}", escape_text(&summary)))?;
+            let description = format!("About {} reading or {} listening\nLength: {} characters",
+                format_minutes(options.reading_minutes(day.length)), format_minutes(options.listening_minutes(day.length)), day.length);
+            write_line(&mut file, &format!("DESCRIPTION:{}", escape_text(&description)))?;
+            write_line(&mut file, "CATEGORIES:Reading")?;
+        }
+        write_line(&mut file, "TRANSP:TRANSPARENT")?;
+        write_line(&mut file, "END:VEVENT")?;
+    }
+
+    write_line(&mut file, "END:VCALENDAR")?;
+    file.flush()
+}
+
+// Write a content line ending in CRLF, folding it so that no line is longer than 75 octets
+fn write_line(file: &mut impl Write, line: &str) -> std::io::Result<()> {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            file.write_all(b"\r\n ")?;
+            width = 1;
+        }
+        write!(file, "{}", c)?;
+        width += c.len_utf8();
+    }
+    file.write_all(b"\r\n")
+}
+
+// Escape the characters that have a special meaning in a TEXT value
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Reduce the plan title to lowercase letters, digits and dashes for use in a UID
+fn slug(title: &str) -> String {
+    let slug = title.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-");
+    if slug.is_empty() { "plan".to_string() } else { slug }
+}
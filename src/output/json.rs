@@ -0,0 +1,97 @@
+use std::fs::File;
+use std::io::{ BufWriter, Write };
+use std::path::Path;
+use chrono::NaiveDate;
+use serde::Serialize;
+use crate::plan::{ Plan, Reading, TrackDay };
+use super::{ OutputOptions, Summary, output_days, total_summary, week_summaries };
+
+// The JSON document: the plan's metadata followed by its days
+#[derive(Debug, Serialize)]
+struct JsonPlan<'a> {
+    title: &'a str,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    duration: i32,
+    tracks: usize,
+    total_length: i32,
+    total_reading_minutes: i32,
+    total_listening_minutes: i32,
+    days: Vec<JsonDay<'a>>,
+    weeks: Vec<JsonWeek>
+}
+
+#[derive(Debug, Serialize)]
+struct JsonDay<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    day: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date: Option<NaiveDate>,
+    length: i32,
+    reading_minutes: i32,
+    listening_minutes: i32,
+    catch_up: bool,
+    rest_day: bool,
+    // What each track does on this day: "reading", "catch-up" or "rest"
+    tracks: &'a [TrackDay],
+    readings: &'a [Reading]
+}
+
+#[derive(Debug, Serialize)]
+struct JsonWeek {
+    week: String,
+    reading_days: usize,
+    length: i32,
+    reading_minutes: i32,
+    listening_minutes: i32
+}
+
+impl From<Summary> for JsonWeek {
+    fn from(summary: Summary) -> JsonWeek {
+        JsonWeek {
+            week: summary.heading,
+            reading_days: summary.reading_days,
+            length: summary.length,
+            reading_minutes: summary.reading_minutes,
+            listening_minutes: summary.listening_minutes
+        }
+    }
+}
+
+// Write the plan as a JSON object with its metadata, a list of days, each with its readings and
+// estimated times, and a summary of each week. Option: duration_flag: leave out the dates
+pub fn write_json(filename: &Path, plan: &Plan, options: &OutputOptions) -> std::io::Result<()> {
+    let days: Vec<JsonDay> = output_days(plan, options)
+        .into_iter()
+        .map(|day| JsonDay {
+            day: day.number,
+            date: if options.duration_flag { None } else { Some(day.date) },
+            length: day.length,
+            reading_minutes: options.reading_minutes(day.length),
+            listening_minutes: options.listening_minutes(day.length),
+            catch_up: day.is_catch_up_day(),
+            rest_day: day.is_rest_day(),
+            tracks: day.tracks,
+            readings: day.readings
+        })
+        .collect();
+
+    let total = total_summary(plan, options);
+    let json_plan = JsonPlan {
+        title: &options.title,
+        start_date: plan.start_date,
+        end_date: plan.end_date,
+        duration: plan.duration,
+        tracks: plan.tracks.len(),
+        total_length: plan.lengths.iter().map(|d| d.length).sum(),
+        total_reading_minutes: total.reading_minutes,
+        total_listening_minutes: total.listening_minutes,
+        days,
+        weeks: week_summaries(plan, options).into_iter().map(JsonWeek::from).collect()
+    };
+
+    let mut file = BufWriter::new(File::create(filename)?);
+    serde_json::to_writer_pretty(&mut file, &json_plan)?;
+    writeln!(file)?;
+    file.flush()
+}
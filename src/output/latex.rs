@@ -0,0 +1,122 @@
+use std::fs::File;
+use std::io::{ BufWriter, Write };
+use std::path::Path;
+use crate::plan::Plan;
+use super::{ Layout, OutputOptions, day_label, format_minutes, group_days, total_summary, track_cells, week_summaries };
+
+// The document preamble of each layout: a two-column A5 booklet or a single narrow bookmark strip
+const BOOKLET_PREAMBLE: &str = "\
+\\documentclass[10pt]{article}
+\\usepackage[a5paper,margin=1.5cm]{geometry}
+\\usepackage[utf8]{inputenc}
+\\usepackage[T1]{fontenc}
+\\usepackage{amssymb}
+\\usepackage{multicol}
+\\setlength{\\parindent}{0pt}
+\\setlength{\\columnsep}{1.5em}
+\\pagestyle{empty}";
+
+const BOOKMARK_PREAMBLE: &str = "\
+\\documentclass[8pt]{extarticle}
+\\usepackage[paperwidth=2.5in,paperheight=8in,margin=0.2in]{geometry}
+\\usepackage[utf8]{inputenc}
+\\usepackage[T1]{fontenc}
+\\usepackage{amssymb}
+\\setlength{\\parindent}{0pt}
+\\pagestyle{empty}";
+
+// Write the plan as a LaTeX document for pdflatex, with a header per month or week and a tick box
+// per day. A booklet lists each day on one line in two columns; a bookmark puts each track's readings
+// on its own line so that the list fits a narrow strip. Option: time_flag: add each day's reading time
+// and end with the weekly and total reading and listening times
+pub fn write_latex(filename: &Path, plan: &Plan, options: &OutputOptions) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(filename)?);
+    let bookmark = options.layout == Layout::Bookmark;
+
+    writeln!(file, "{}", if bookmark { BOOKMARK_PREAMBLE } else { BOOKLET_PREAMBLE })?;
+    writeln!(file, "\\begin{{document}}")?;
+    writeln!(file, "\\begin{{center}}")?;
+    writeln!(file, "{{\\{} \\textbf{{{}}}}}\\par", if bookmark { "large" } else { "Large" }, escape_latex(&options.title))?;
+    if let Some(header) = &options.header {
+        writeln!(file, "\\smallskip{{\\small {}}}\\par", escape_latex(header))?;
+    }
+    writeln!(file, "\\end{{center}}")?;
+    if !bookmark {
+        writeln!(file, "\\begin{{multicols}}{{2}}")?;
+    }
+
+    for group in group_days(plan, options) {
+        writeln!(file, "\n\\medskip\\textbf{{{}}}\\par\\smallskip", escape_latex(&group.heading))?;
+
+        for day in &group.days {
+            let label = escape_latex(&day_label(day, options.duration_flag));
+            let mut details: Vec<String> = Vec::new();
+            if options.length_flag {
+                details.push(day.length.to_string());
+            }
+            if options.time_flag {
+                details.push(format_minutes(options.reading_minutes(day.length)));
+            }
+            let details = if !details.is_empty() && !day.readings.is_empty() {
+                format!(" {{\\footnotesize({})}}", details.join(", "))
+            } else {
+                String::new()
+            };
+
+            // One cell per track, with "Catch-up" for a track that catches up while another reads
+            let cells: Vec<String> = track_cells(day).into_iter().filter(|cell| !cell.is_empty()).collect();
+
+            if day.is_rest_day() {
+                writeln!(file, "$\\phantom{{\\square}}$~{}\\hfill\\textit{{Rest day}}\\par", label)?;
+            } else if day.readings.is_empty() {
+                writeln!(file, "$\\square$~{}\\hfill\\textit{{Catch-up day}}\\par", label)?;
+            } else if bookmark {
+                writeln!(file, "$\\square$~\\textbf{{{}}}{}\\par", label, details)?;
+                for cell in cells {
+                    writeln!(file, "\\hspace*{{1.2em}}{}\\par", escape_latex(&cell))?;
+                }
+            } else {
+                let readings = cells.iter().map(|cell| escape_latex(cell)).collect::<Vec<String>>().join("; ");
+                writeln!(file, "$\\square$~\\textbf{{{}}}\\hfill {}{}\\par", label, readings, details)?;
+            }
+        }
+    }
+
+    if options.time_flag {
+        writeln!(file, "\n\\medskip\\textbf{{Weekly summary}}\\par\\smallskip")?;
+        let mut summaries = week_summaries(plan, options);
+        summaries.push(total_summary(plan, options));
+        for summary in summaries {
+            writeln!(file, "{}\\hfill {} days, {} reading, {} listening\\par",
+                escape_latex(&summary.heading),
+                summary.reading_days,
+                format_minutes(summary.reading_minutes),
+                format_minutes(summary.listening_minutes)
+            )?;
+        }
+    }
+
+    if !bookmark {
+        writeln!(file, "\\end{{multicols}}")?;
+    }
+    writeln!(file, "\\end{{document}}")?;
+    file.flush()
+}
+
+// Escape the characters that have a special meaning in LaTeX text
+fn escape_latex(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
@@ -0,0 +1,81 @@
+use std::fs::File;
+use std::io::{ BufWriter, Write };
+use std::path::Path;
+use crate::plan::Plan;
+use super::{ OutputOptions, day_label, format_minutes, group_days, total_summary, track_cells, track_headings, week_summaries };
+
+// Write the plan as a Markdown checklist with a heading per month or week and a table with a
+// tick box per day and a column per track. Catch-up days are in bold and rest days in italics.
+// Option: time_flag: add a reading time column and a table of weekly and total reading and listening times
+pub fn write_markdown(filename: &Path, plan: &Plan, options: &OutputOptions) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(filename)?);
+    let tracks = plan.tracks.len();
+
+    writeln!(file, "# {}\n", options.title)?;
+    if let Some(header) = &options.header {
+        writeln!(file, "{}\n", header)?;
+    }
+
+    let mut headings = vec!["".to_string(), "Day".to_string()];
+    headings.extend(track_headings(tracks));
+    if options.length_flag {
+        headings.push("Length".to_string());
+    }
+    if options.time_flag {
+        headings.push("Time".to_string());
+    }
+
+    for group in group_days(plan, options) {
+        writeln!(file, "## {}\n", group.heading)?;
+        writeln!(file, "| {} |", headings.join(" | "))?;
+        writeln!(file, "|{}", "---|".repeat(headings.len()))?;
+
+        for day in &group.days {
+            let mut cells = vec![if day.is_rest_day() { "" } else { "☐" }.to_string()];
+            if day.is_rest_day() {
+                cells.push(day_label(day, options.duration_flag));
+                cells.push("*Rest day*".to_string());
+                cells.extend(vec![String::new(); tracks - 1]);
+            } else if day.readings.is_empty() {
+                cells.push(format!("**{}**", day_label(day, options.duration_flag)));
+                cells.push("**Catch-up day**".to_string());
+                cells.extend(vec![String::new(); tracks - 1]);
+            } else {
+                cells.push(day_label(day, options.duration_flag));
+                cells.extend(track_cells(day).iter().map(|cell| escape_markdown(cell)));
+            }
+            if options.length_flag {
+                cells.push(if day.is_rest_day() { String::new() } else { day.length.to_string() });
+            }
+            if options.time_flag {
+                cells.push(if day.readings.is_empty() { String::new() } else { format_minutes(options.reading_minutes(day.length)) });
+            }
+            writeln!(file, "| {} |", cells.join(" | "))?;
+        }
+
+        writeln!(file)?;
+    }
+
+    if options.time_flag {
+        writeln!(file, "## Weekly summary\n")?;
+        writeln!(file, "| Week | Reading days | Reading | Listening |")?;
+        writeln!(file, "|---|---|---|---|")?;
+        let total = total_summary(plan, options);
+        for summary in week_summaries(plan, options) {
+            writeln!(file, "| {} | {} | {} | {} |",
+                summary.heading, summary.reading_days, format_minutes(summary.reading_minutes), format_minutes(summary.listening_minutes))?;
+        }
+        writeln!(file, "| **{}** | **{}** | **{}** | **{}** |",
+            total.heading, total.reading_days, format_minutes(total.reading_minutes), format_minutes(total.listening_minutes))?;
+        writeln!(file)?;
+    }
+
+    file.flush()
+}
+
+// Escape the characters that would break a table cell or start emphasis
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+        .replace('*', "\\*")
+        .replace('_', "\\_")
+}
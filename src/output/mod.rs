@@ -0,0 +1,426 @@
+use std::path::Path;
+use std::str::FromStr;
+use chrono::{ Datelike, Duration, NaiveDate };
+use serde::Deserialize;
+use crate::plan::{ DEFAULT_LISTENING_SPEED, DEFAULT_READING_SPEED, Plan, Reading, TrackDay };
+
+mod csv;
+mod html;
+mod ics;
+mod json;
+mod latex;
+mod markdown;
+mod text;
+
+pub use self::csv::write_csv;
+pub use html::write_html;
+pub use ics::write_ics;
+pub use json::write_json;
+pub use latex::write_latex;
+pub use markdown::write_markdown;
+pub use text::{ write_text, write_to_file };
+
+// The file format of a written reading plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    // One line per day, e.g. "Jun 21, 2025  Matthew 1-3"
+    #[default]
+    Text,
+    // An iCalendar file with one all-day event per day
+    Ics,
+    // One row per reading per track
+    Csv,
+    // The plan's metadata and its days with their readings
+    Json,
+    // A printable checklist page
+    Html,
+    // A checklist of Markdown tables
+    Markdown,
+    // A LaTeX booklet or bookmark for typesetting with pdflatex
+    Latex
+}
+
+impl Format {
+    // Choose a format from a file extension, e.g. "plan.ics"
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension().and_then(|e| e.to_str())?.to_lowercase().as_str() {
+            "txt" => Some(Format::Text),
+            "ics" => Some(Format::Ics),
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            "html" | "htm" => Some(Format::Html),
+            "md" | "markdown" => Some(Format::Markdown),
+            "tex" => Some(Format::Latex),
+            _ => None
+        }
+    }
+
+    // The file extension added to a default output file name
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Format::Text => None,
+            Format::Ics => Some("ics"),
+            Format::Csv => Some("csv"),
+            Format::Json => Some("json"),
+            Format::Html => Some("html"),
+            Format::Markdown => Some("md"),
+            Format::Latex => Some("tex")
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "ics" => Ok(Format::Ics),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            "html" => Ok(Format::Html),
+            "markdown" => Ok(Format::Markdown),
+            "latex" => Ok(Format::Latex),
+            _ => Err(format!("unknown format '{}', expected text, ics, csv, json, html, markdown or latex", s))
+        }
+    }
+}
+
+// How the days of a checklist are grouped under headings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grouping {
+    // Calendar months, or blocks of 30 days when the days are numbered
+    #[default]
+    Month,
+    // Weeks starting on Monday, or blocks of 7 days when the days are numbered
+    Week
+}
+
+impl FromStr for Grouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Grouping, String> {
+        match s {
+            "month" => Ok(Grouping::Month),
+            "week" => Ok(Grouping::Week),
+            _ => Err(format!("unknown grouping '{}', expected month or week", s))
+        }
+    }
+}
+
+// The page layout of a LaTeX plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    // A two-column A5 booklet with one line per day
+    #[default]
+    Booklet,
+    // A narrow strip with each reading on its own line
+    Bookmark
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Layout, String> {
+        match s {
+            "booklet" => Ok(Layout::Booklet),
+            "bookmark" => Ok(Layout::Bookmark),
+            _ => Err(format!("unknown layout '{}', expected booklet or bookmark", s))
+        }
+    }
+}
+
+// Settings for writing a reading plan
+#[derive(Debug, Clone)]
+pub struct OutputOptions {
+    pub format: Format,
+    // Include the daily reading lengths
+    pub length_flag: bool,
+    // Include the estimated daily reading and listening times, with weekly and total summaries
+    pub time_flag: bool,
+    // Characters read per minute, and characters heard per minute of an audio Bible
+    pub reading_speed: i32,
+    pub listening_speed: i32,
+    // Number the days rather than dating them, where the format allows it
+    pub duration_flag: bool,
+    // The plan's name, used for the calendar name and event UIDs and as the checklist title
+    pub title: String,
+    // Text shown under the checklist title
+    pub header: Option<String>,
+    // How the days of a checklist are grouped
+    pub grouping: Grouping,
+    // The page layout of a LaTeX plan
+    pub layout: Layout,
+    // Include the days on which nothing is read, marked as rest days
+    pub rest_days: bool
+}
+
+impl Default for OutputOptions {
+    fn default() -> OutputOptions {
+        OutputOptions {
+            format: Format::default(),
+            length_flag: false,
+            time_flag: false,
+            reading_speed: DEFAULT_READING_SPEED,
+            listening_speed: DEFAULT_LISTENING_SPEED,
+            duration_flag: false,
+            title: "Bible Reading Plan".to_string(),
+            header: None,
+            grouping: Grouping::default(),
+            layout: Layout::default(),
+            rest_days: false
+        }
+    }
+}
+
+impl OutputOptions {
+    // The estimated minutes to read `length` characters
+    pub fn reading_minutes(&self, length: i32) -> i32 {
+        estimate_minutes(length, self.reading_speed)
+    }
+
+    // The estimated minutes to listen to `length` characters
+    pub fn listening_minutes(&self, length: i32) -> i32 {
+        estimate_minutes(length, self.listening_speed)
+    }
+}
+
+// Minutes to get through `length` characters at `speed` characters a minute, rounded to the nearest
+// minute but at least one for any reading
+fn estimate_minutes(length: i32, speed: i32) -> i32 {
+    if length <= 0 {
+        return 0;
+    }
+    ((length as f64 / speed.max(1) as f64).round() as i32).max(1)
+}
+
+// Format a number of minutes as e.g. "45 min", "1 h 5 min" or "2 h"
+fn format_minutes(minutes: i32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{} min", minutes),
+        (hours, 0) => format!("{} h", hours),
+        (hours, minutes) => format!("{} h {} min", hours, minutes)
+    }
+}
+
+// Write the plan to `filename` in the chosen format
+pub fn write_plan(filename: &Path, plan: &Plan, options: &OutputOptions) -> std::io::Result<()> {
+    match options.format {
+        Format::Text => write_text(filename, plan, options),
+        Format::Ics => write_ics(filename, plan, options),
+        Format::Csv => write_csv(filename, plan, options),
+        Format::Json => write_json(filename, plan, options),
+        Format::Html => write_html(filename, plan, options),
+        Format::Markdown => write_markdown(filename, plan, options),
+        Format::Latex => write_latex(filename, plan, options)
+    }
+}
+
+// One day of a written plan: a reading day, a catch-up day or a rest day
+struct OutputDay<'a> {
+    // The day's number, counting from 1. Rest days are not numbered
+    number: Option<i32>,
+    date: NaiveDate,
+    readings: &'a [Reading],
+    // What each track does on this day
+    tracks: &'a [TrackDay],
+    length: i32
+}
+
+impl OutputDay<'_> {
+    fn is_rest_day(&self) -> bool {
+        self.number.is_none()
+    }
+
+    fn is_catch_up_day(&self) -> bool {
+        self.number.is_some() && self.readings.is_empty()
+    }
+}
+
+// The days to write, numbered from 1. With rest_days, the plan's rest days are added in date order,
+// unless the days are numbered
+fn output_days<'a>(plan: &'a Plan, options: &OutputOptions) -> Vec<OutputDay<'a>> {
+    let mut number: i32 = 0;
+    plan.schedule.iter()
+        .filter_map(|day| {
+            let rest_day = plan.rest_days.binary_search(&day.date).is_ok();
+            if rest_day && (!options.rest_days || options.duration_flag) {
+                return None;
+            }
+            if !rest_day {
+                number += 1;
+            }
+            Some(OutputDay {
+                number: (!rest_day).then_some(number),
+                date: day.date,
+                readings: day.readings.as_slice(),
+                tracks: day.tracks.as_slice(),
+                length: day.length
+            })
+        })
+        .collect()
+}
+
+// The reading days, length and estimated times of a week of the plan or of the whole plan. The times
+// are the sums of the days' estimates
+struct Summary {
+    heading: String,
+    reading_days: usize,
+    length: i32,
+    reading_minutes: i32,
+    listening_minutes: i32
+}
+
+impl Summary {
+    fn new(heading: String, days: &[OutputDay], options: &OutputOptions) -> Summary {
+        let reading_days: Vec<&OutputDay> = days.iter().filter(|day| !day.readings.is_empty()).collect();
+        Summary {
+            heading,
+            reading_days: reading_days.len(),
+            length: reading_days.iter().map(|day| day.length).sum(),
+            reading_minutes: reading_days.iter().map(|day| options.reading_minutes(day.length)).sum(),
+            listening_minutes: reading_days.iter().map(|day| options.listening_minutes(day.length)).sum()
+        }
+    }
+}
+
+// Summarize each week of the plan, as grouped for a checklist by week
+fn week_summaries(plan: &Plan, options: &OutputOptions) -> Vec<Summary> {
+    group_days(plan, &OutputOptions { grouping: Grouping::Week, ..options.clone() })
+        .into_iter()
+        .map(|group| Summary::new(group.heading, &group.days, options))
+        .collect()
+}
+
+// Summarize the whole plan
+fn total_summary(plan: &Plan, options: &OutputOptions) -> Summary {
+    Summary::new("Total".to_string(), &output_days(plan, options), options)
+}
+
+// Days grouped under a heading such as "June 2025", "Week of Jun 16, 2025" or "Days 31-60"
+struct DayGroup<'a> {
+    heading: String,
+    days: Vec<OutputDay<'a>>
+}
+
+// Group the days of the plan for a checklist. With duration_flag, the days are grouped by number
+fn group_days<'a>(plan: &'a Plan, options: &OutputOptions) -> Vec<DayGroup<'a>> {
+    let block_size = match options.grouping {
+        Grouping::Month => 30,
+        Grouping::Week => 7
+    };
+
+    let mut groups: Vec<DayGroup> = Vec::new();
+    let mut last_key: Option<i64> = None;
+    for day in output_days(plan, options) {
+        let date = day.date;
+        let (key, heading) = match (day.number, options.duration_flag) {
+            (Some(number), true) => {
+                let block = (number - 1) / block_size;
+                let heading = match options.grouping {
+                    Grouping::Month => format!("Days {}-{}", block * block_size + 1, (block + 1) * block_size),
+                    Grouping::Week => format!("Week {}", block + 1)
+                };
+                (block as i64, heading)
+            }
+            _ => match options.grouping {
+                Grouping::Month => ((date.year() * 12 + date.month() as i32) as i64, date.format("%B %Y").to_string()),
+                Grouping::Week => {
+                    let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                    (monday.num_days_from_ce() as i64, format!("Week of {}", monday.format("%b %-d, %Y")))
+                }
+            }
+        };
+
+        if last_key != Some(key) {
+            groups.push(DayGroup { heading, days: Vec::new() });
+            last_key = Some(key);
+        }
+        groups.last_mut().unwrap().days.push(day);
+    }
+
+    // Don't let the heading of a final partial block of numbered days run past the last day
+    if options.duration_flag && options.grouping == Grouping::Month {
+        if let Some(group) = groups.last_mut() {
+            let first = group.days[0].number.unwrap_or(0);
+            let last = group.days[group.days.len() - 1].number.unwrap_or(0);
+            group.heading = if first == last { format!("Day {}", first) } else { format!("Days {}-{}", first, last) };
+        }
+    }
+
+    groups
+}
+
+// The readings of each track on one day, joined into one cell per track
+fn track_cells(day: &OutputDay) -> Vec<String> {
+    day.tracks.iter()
+        .enumerate()
+        .map(|(i, track_day)| match track_day {
+            TrackDay::Reading => day.readings.iter()
+                .filter(|r| r.track == i + 1)
+                .map(|r| r.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+            TrackDay::CatchUp => "Catch-up".to_string(),
+            TrackDay::Rest => String::new()
+        })
+        .collect()
+}
+
+// The column headings of the track cells
+fn track_headings(tracks: usize) -> Vec<String> {
+    if tracks == 1 {
+        vec!["Reading".to_string()]
+    } else {
+        (1..=tracks).map(|track| format!("Track {}", track)).collect()
+    }
+}
+
+// Label a checklist day with its date, or its number when the days are numbered
+fn day_label(day: &OutputDay, duration_flag: bool) -> String {
+    match day.number {
+        Some(number) if duration_flag => format!("Day {}", number),
+        _ => day.date.format("%a %b %-d").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::PlanRequest;
+    use crate::planner::CatchUp;
+
+    const FORMATS: [Format; 7] = [Format::Text, Format::Ics, Format::Csv, Format::Json, Format::Html, Format::Markdown, Format::Latex];
+
+    #[test]
+    fn track_catch_up_is_written_in_every_format() {
+        // The Gospels end books more often than Psalms, so the first track catches up while Psalms is read
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let plan = PlanRequest::new(start).tracks(vec![(40..=43).collect(), vec![19]]).days(60).catch_up(CatchUp::BookEnd).generate().unwrap();
+        let day = plan.schedule.iter().find(|day| day.tracks == [TrackDay::CatchUp, TrackDay::Reading]).unwrap();
+        let reading = day.readings[0].to_string();
+
+        for format in FORMATS {
+            let filename = std::env::temp_dir().join(format!("bible_planner_track_catch_up_{:?}", format));
+            write_plan(&filename, &plan, &OutputOptions { format, ..OutputOptions::default() }).unwrap();
+            let contents = std::fs::read_to_string(&filename).unwrap();
+            std::fs::remove_file(&filename).unwrap();
+
+            let written = match format {
+                Format::Csv => contents.lines()
+                    .map(|line| line.split(',').collect::<Vec<&str>>())
+                    .any(|row| row[0] == day.date.to_string() && row[2] == "1" && row[11] == "true"),
+                Format::Json => {
+                    let json: serde_json::Value = serde_json::from_str(&contents).unwrap();
+                    json["days"].as_array().unwrap().iter()
+                        .any(|json_day| json_day["date"] == day.date.to_string() && json_day["tracks"][0] == "catch-up")
+                }
+                _ => contents.lines().any(|line| line.contains("Catch-up") && line.contains(&reading))
+            };
+            assert!(written, "{:?} has no catch-up for track 1 on {}", format, day.date);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use crate::plan::Plan;
+use super::{ OutputOptions, Summary, format_minutes, output_days, total_summary, track_cells, week_summaries };
+
+// Write the output file, with reading date, book(s) and chapter(s) (or 'Catch-up day' if all readings for that
+// date are catch-up days). Option: length_flag: include daily reading lengths. Option: duration_flag: use day count
+// rather than dates.
+pub fn write_to_file(
+    filename: &Path,
+    plan: &Plan,
+    length_flag: bool,
+    duration_flag: bool) -> std::io::Result<()>
+{
+    let options = OutputOptions { length_flag, duration_flag, ..OutputOptions::default() };
+    write_text(filename, plan, &options)
+}
+
+// Write the text output with the given options, including any rest days as 'Rest day'. Option: time_flag:
+// add each day's estimated reading and listening times, followed by weekly and total summaries
+pub fn write_text(filename: &Path, plan: &Plan, options: &OutputOptions) -> std::io::Result<()> {
+    let mut file = File::create(filename)?;
+
+    // Write each day's readings to the file, or mark the date as a catch-up or rest day. A track that
+    // catches up while another reads shows 'Catch-up' in its place
+    for day in output_days(plan, options) {
+        let output = track_cells(&day).into_iter().filter(|cell| !cell.is_empty()).collect::<Vec<String>>().join(", ");
+
+        // The length and times shown after the readings, e.g. " (16034, 13 min reading, 19 min listening)"
+        let mut details: Vec<String> = Vec::new();
+        if options.length_flag {
+            details.push(day.length.to_string());
+        }
+        if options.time_flag {
+            details.push(format!("{} reading", format_minutes(options.reading_minutes(day.length))));
+            details.push(format!("{} listening", format_minutes(options.listening_minutes(day.length))));
+        }
+        let details = if details.is_empty() { String::new() } else { format!(" ({})", details.join(", ")) };
+
+        if day.is_rest_day() {
+            writeln!(file, "{}  Rest day", day.date.format("%b %e, %Y"))?;
+        } else if day.is_catch_up_day() && !options.duration_flag {
+            // If the current date is marked as a catch-up day, write it to the file
+            writeln!(file, "{}  Catch-up day", day.date.format("%b %e, %Y"))?;
+        } else if day.is_catch_up_day() {
+            writeln!(file, "{}  Catch-up day", day.number.unwrap_or(0))?;
+        } else if options.duration_flag {
+            // If duration_flag is true, the day number replaces the date in the output
+            writeln!(file, "{}  {}{}", day.number.unwrap_or(0), output, details)?;
+        } else {
+            // Otherwise, write the readings for the current date to the file
+            writeln!(file, "{}  {}{}", day.date.format("%b %e, %Y"), output, details)?;
+        }
+    }
+
+    if options.time_flag {
+        let mut summaries: Vec<Summary> = week_summaries(plan, options);
+        summaries.push(total_summary(plan, options));
+        let width = summaries.iter().map(|summary| summary.heading.len()).max().unwrap_or(0);
+
+        writeln!(file, "\nWeekly summary")?;
+        for summary in summaries {
+            writeln!(file, "{:<width$}  {:>3} reading days  {:>11} reading  {:>11} listening",
+                summary.heading,
+                summary.reading_days,
+                format_minutes(summary.reading_minutes),
+                format_minutes(summary.listening_minutes),
+                width = width
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,222 @@
+/*
+    Passages: runs of consecutive chapters of one book, so that a track can read part of a book,
+    or read a book in several parts with other books in between. The built-in chronological
+    order is a list of passages, e.g. Genesis 1-11, then Job, then Genesis 12-50:
+
+        let plan = PlanRequest::new(NaiveDate::from_ymd_opt(2026, 1, 1).unwrap())
+            .passage_track(chronological_order()?)
+            .days(365)
+            .generate()?;
+
+    The planner plans each passage that reads only part of a book as a book of its own, then
+    gives the readings the title and chapter numbers of the book the passage is from.
+*/
+
+use std::collections::HashMap;
+use csv::ReaderBuilder;
+use serde::Deserialize;
+use crate::data::{ BibleData, BookData, malformed_csv };
+use crate::error::PlannerError;
+use crate::plan::{ Plan, Reading };
+use crate::planner::ChaptersDate;
+
+// The chronological reading order, compiled into the binary like the chapter data
+pub const CHRONOLOGICAL_CSV: &str = include_str!("chronological.csv");
+
+// A run of chapters of one book, by book index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Passage {
+    pub index: i32,
+    // The first and last chapter read, or None for the whole book
+    pub chapters: Option<(i32, i32)>
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct PassageRecord {
+    pub index: i32,
+    pub title: String,
+    pub start_chapter: i32,
+    pub end_chapter: i32
+}
+
+impl Passage {
+    // The whole of a book
+    pub fn book(index: i32) -> Passage {
+        Passage { index, chapters: None }
+    }
+
+    // The chapters `first` to `last` of a book
+    pub fn chapters(index: i32, first: i32, last: i32) -> Passage {
+        Passage { index, chapters: Some((first, last)) }
+    }
+
+    // Find the passage's book and its first and last chapter in the chapter data
+    pub fn resolve<'a>(&self, bible: &'a BibleData) -> Result<(&'a BookData, i32, i32), PlannerError> {
+        let book = bible.book(self.index)
+            .ok_or_else(|| PlannerError::InvalidSelection(format!("book index {} is not in the chapter data", self.index)))?;
+        match self.chapters {
+            None => Ok((book, 1, book.chapters())),
+            Some((first, last)) if 1 <= first && first <= last && last <= book.chapters() => Ok((book, first, last)),
+            Some((first, last)) => Err(PlannerError::InvalidSelection(
+                format!("{} {}-{} is not in the chapter data, which has {} chapters", book.title, first, last, book.chapters())))
+        }
+    }
+}
+
+// Load the built-in chronological order: every chapter of the Bible once, with the books read in
+// the order their events happened. Job follows Genesis 11, the psalms are read with the events
+// they are linked to, Chronicles alongside Samuel and Kings, the prophets during the reigns they
+// address, the gospels in a harmony and the epistles among the chapters of Acts
+pub fn chronological_order() -> Result<Vec<Passage>, PlannerError> {
+    let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(CHRONOLOGICAL_CSV.as_bytes());
+    let headers = rdr.headers().map_err(malformed_csv)?.clone();
+
+    let mut passages: Vec<Passage> = Vec::new();
+    for result in rdr.records() {
+        let row = result.map_err(malformed_csv)?;
+        let line = row.position().map(|p| p.line()).unwrap_or(0);
+        let record: PassageRecord = row.deserialize(Some(&headers)).map_err(malformed_csv)?;
+        if record.start_chapter < 1 || record.start_chapter > record.end_chapter {
+            return Err(PlannerError::MalformedCsv {
+                line,
+                message: format!("{} {}-{} is not a chapter range", record.title, record.start_chapter, record.end_chapter)
+            });
+        }
+        passages.push(Passage::chapters(record.index, record.start_chapter, record.end_chapter));
+    }
+    Ok(passages)
+}
+
+// The book a passage of part of a book was taken from
+#[derive(Debug, Clone)]
+struct Original {
+    title: String,
+    index: i32,
+    // The number of chapters of the book before the passage
+    offset: i32,
+    // Whether the passage reads to the end of the book
+    ends_book: bool
+}
+
+// The chapter data with a book of its own for each passage that reads only part of a book, and
+// the tracks as indexes of those books. A passage of a whole book keeps the book itself
+pub(crate) struct PassageBooks {
+    pub bible: BibleData,
+    pub book_indexes: Vec<Vec<i32>>,
+    // The book each passage book was taken from, by the passage book's title
+    originals: HashMap<String, Original>
+}
+
+impl PassageBooks {
+    // Make the books of the passages of each track. A passage book is titled after its book and
+    // numbered, e.g. "Genesis (1)", and its index keeps it in the same testament
+    pub fn new(bible: &BibleData, tracks: &[Vec<Passage>]) -> Result<PassageBooks, PlannerError> {
+        let mut books: Vec<BookData> = bible.books().to_vec();
+        let mut originals: HashMap<String, Original> = HashMap::new();
+        let mut indexes_by_passage: HashMap<(i32, i32, i32), i32> = HashMap::new();
+        let mut book_indexes: Vec<Vec<i32>> = Vec::new();
+
+        for track in tracks {
+            let mut indexes: Vec<i32> = Vec::new();
+            for passage in track {
+                let (book, first, last) = passage.resolve(bible)?;
+                if first == 1 && last == book.chapters() {
+                    indexes.push(book.index);
+                    continue;
+                }
+
+                // A repeated passage reads the same passage book again
+                let index = *indexes_by_passage.entry((book.index, first, last)).or_insert_with(|| {
+                    let number = originals.len() as i32 + 1;
+                    let title = format!("{} ({})", book.title, number);
+                    let index = if book.index >= 40 { 1000 + number } else { -number };
+                    originals.insert(title.clone(), Original {
+                        title: book.title.clone(),
+                        index: book.index,
+                        offset: first - 1,
+                        ends_book: last == book.chapters()
+                    });
+                    books.push(BookData {
+                        index,
+                        title,
+                        chapter_lengths: book.chapter_lengths[first as usize - 1..last as usize].to_vec()
+                    });
+                    index
+                });
+                indexes.push(index);
+            }
+            book_indexes.push(indexes);
+        }
+
+        Ok(PassageBooks { bible: BibleData::from_books(books), book_indexes, originals })
+    }
+
+    // The index of the book a book of the planning data was taken from, and the number of chapters
+    // before it, so that chapter n of the planning book is chapter n + offset of the original
+    pub fn source(&self, book: &BookData) -> (i32, i32) {
+        self.originals.get(&book.title).map_or((book.index, 0), |original| (original.index, original.offset))
+    }
+
+    // The index of the book a book of the planning data was taken from, and whether reading it to its
+    // end finishes that book, as it does unless it is a passage that stops short of the book's end
+    pub fn book_end(&self, book: &BookData) -> (i32, bool) {
+        self.originals.get(&book.title).map_or((book.index, true), |original| (original.index, original.ends_book))
+    }
+
+    // Give the readings of a plan made from the passage books the titles and chapter numbers of
+    // the books the passages were taken from
+    pub fn restore(&self, plan: &mut Plan) {
+        for chapters_date in plan.tracks.iter_mut().chain(plan.days.iter_mut()).flatten() {
+            self.restore_chapters_date(chapters_date);
+        }
+        let schedule_readings = plan.schedule.iter_mut().map(|day| &mut day.readings);
+        for readings in plan.readings.iter_mut().chain(schedule_readings) {
+            *readings = std::mem::take(readings).into_iter().flat_map(|reading| self.restore_reading(reading)).collect();
+        }
+    }
+
+    fn restore_chapters_date(&self, chapters_date: &mut ChaptersDate) {
+        if let [title] = chapters_date.titles.as_slice() {
+            if let Some(original) = self.originals.get(title) {
+                chapters_date.chapters += original.offset;
+            }
+        }
+        for title in chapters_date.titles.iter_mut() {
+            if let Some(original) = self.originals.get(title) {
+                *title = original.title.clone();
+            }
+        }
+    }
+
+    // A reading of several books in full that include a passage becomes a reading of each, since
+    // the passage's chapters don't run from 1
+    fn restore_reading(&self, mut reading: Reading) -> Vec<Reading> {
+        if reading.titles.len() > 1 && reading.titles.iter().any(|title| self.originals.contains_key(title)) {
+            return reading.titles.iter()
+                .filter_map(|title| self.bible.book_by_title(title))
+                .flat_map(|book| self.restore_reading(Reading {
+                    track: reading.track,
+                    titles: vec![book.title.clone()],
+                    start_chapter: 1,
+                    end_chapter: book.chapters(),
+                    start_verse: None,
+                    end_verse: None,
+                    length: book.length()
+                }))
+                .collect();
+        }
+
+        if let [title] = reading.titles.as_slice() {
+            if let Some(original) = self.originals.get(title) {
+                reading.start_chapter += original.offset;
+                reading.end_chapter += original.offset;
+            }
+        }
+        for title in reading.titles.iter_mut() {
+            if let Some(original) = self.originals.get(title) {
+                *title = original.title.clone();
+            }
+        }
+        vec![reading]
+    }
+}
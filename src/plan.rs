@@ -0,0 +1,692 @@
+use std::fmt;
+use std::collections::{ BTreeMap, HashMap };
+use chrono::{ Duration, NaiveDate, Weekday };
+use serde::{ Deserialize, Serialize };
+use crate::calendar::{ get_catch_up_slots, get_reading_dates, most_catch_up_days };
+use crate::data::{ BibleData, BookData, ChapterData, VerseData };
+use crate::passage::{ Passage, PassageBooks };
+use crate::error::PlannerError;
+use crate::planner::{
+    Algorithm, Balance, BookEnd, CatchUp, CatchUpSync, ChapterPart, ChaptersDate, ChaptersDays, DailyLength, SplitBible,
+    adjust_dates, balance_combined_lengths, count_boundaries, fit_books_in_days, get_books_in_days, get_chapters_dates_by_length,
+    get_chapters_dates_by_track, get_daily_reading_lengths, get_duration, get_joint_catch_up_slots,
+    place_catch_ups, split_long_chapters
+};
+
+// Characters read per minute, used to turn a daily reading time into a daily length and to estimate
+// reading times
+pub const DEFAULT_READING_SPEED: i32 = 1200;
+
+// Characters heard per minute of an audio Bible, used to estimate listening times
+pub const DEFAULT_LISTENING_SPEED: i32 = 850;
+
+/*
+    The settings for a reading plan. For example, to read through the New Testament once
+    and Psalms & Proverbs twice over the summer:
+
+        let plan = PlanRequest::new(NaiveDate::from_ymd_opt(2025, 6, 21).unwrap())
+            .track((40..=66).collect())
+            .track((19..=20).chain(19..=20).collect())
+            .end_date(NaiveDate::from_ymd_opt(2025, 9, 21).unwrap())
+            .generate()?;
+*/
+#[derive(Debug, Clone)]
+pub struct PlanRequest {
+    // The passages read by each track
+    tracks: Vec<Vec<Passage>>,
+    start_date: NaiveDate,
+    end_date: Option<NaiveDate>,
+    days: Option<i32>,
+    daily_length: Option<i32>,
+    algorithm: Algorithm,
+    balance: Balance,
+    allow_spanning: bool,
+    catch_up: CatchUp,
+    catch_up_sync: CatchUpSync,
+    combined_balance: bool,
+    weekdays: Vec<Weekday>,
+    blackouts: Vec<(NaiveDate, NaiveDate)>,
+    // Verse lengths for splitting long chapters, and the fraction of a track's average daily length
+    // above which a chapter is split
+    split_chapters: Option<(VerseData, f64)>,
+    bible_data: Option<BibleData>
+}
+
+// A generated reading plan
+#[derive(Debug, Clone, Serialize)]
+pub struct Plan {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    // Total number of reading days in the plan, including catch-up days
+    pub duration: i32,
+    // The days between the start and end dates on which nothing is read, because of the
+    // weekday selection or a blackout
+    pub rest_days: Vec<NaiveDate>,
+    // The adjusted readings of each track, in the order the tracks were added
+    pub tracks: Vec<Vec<ChaptersDate>>,
+    // The readings of all tracks grouped by day
+    pub days: Vec<Vec<ChaptersDate>>,
+    // The chapter ranges read on each day, in the same order as `days`. Empty for a catch-up day
+    pub readings: Vec<Vec<Reading>>,
+    // The summed reading length of all tracks for each date
+    pub lengths: Vec<DailyLength>,
+    // Every date from the start to the end date, including rest days, with what each track does on it
+    pub schedule: Vec<ScheduleDay>
+}
+
+// One date of a plan, with the readings of all tracks and what each track does on that date
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScheduleDay {
+    pub date: NaiveDate,
+    // What each track does, in the order the tracks were added
+    pub tracks: Vec<TrackDay>,
+    // The chapter ranges read by all tracks, in track order
+    pub readings: Vec<Reading>,
+    // The summed reading length of all tracks
+    pub length: i32
+}
+
+// What one track does on one date of the schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrackDay {
+    Reading,
+    CatchUp,
+    // Nothing to read: a rest day, or the track has no reading on this date
+    Rest
+}
+
+// One track's reading on one day: a range of chapters of one book, or several short books read in full.
+// For several books, the chapters run from 1 to their total chapter count
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reading {
+    // Number of the track, starting from 1 in the order the tracks were added
+    pub track: usize,
+    pub titles: Vec<String>,
+    pub start_chapter: i32,
+    pub end_chapter: i32,
+    // The first verse of the start chapter and the last verse of the end chapter, where the reading
+    // starts or ends in the middle of a split chapter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_verse: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_verse: Option<i32>,
+    pub length: i32
+}
+
+impl fmt::Display for Reading {
+    // Format as e.g. "Matthew 1-4", "Jude 1", "Obadiah, Jonah all", "Psalms 119:1-88" or "Psalms 119:89-121"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let titles = self.titles.join(", ");
+        if self.titles.len() > 1 {
+            write!(f, "{} all", titles)
+        } else if self.start_verse.is_some() || self.end_verse.is_some() {
+            let start = match self.start_verse {
+                Some(verse) => format!("{}:{}", self.start_chapter, verse),
+                None => self.start_chapter.to_string()
+            };
+            let end = match self.end_verse {
+                Some(verse) if self.start_verse.is_some() && self.start_chapter == self.end_chapter => verse.to_string(),
+                Some(verse) => format!("{}:{}", self.end_chapter, verse),
+                None => self.end_chapter.to_string()
+            };
+            write!(f, "{} {}-{}", titles, start, end)
+        } else if self.start_chapter == self.end_chapter {
+            write!(f, "{} {}", titles, self.end_chapter)
+        } else {
+            write!(f, "{} {}-{}", titles, self.start_chapter, self.end_chapter)
+        }
+    }
+}
+
+impl PlanRequest {
+    pub fn new(start_date: NaiveDate) -> PlanRequest {
+        PlanRequest {
+            tracks: Vec::new(),
+            start_date,
+            end_date: None,
+            days: None,
+            daily_length: None,
+            algorithm: Algorithm::default(),
+            balance: Balance::default(),
+            allow_spanning: true,
+            catch_up: CatchUp::default(),
+            catch_up_sync: CatchUpSync::default(),
+            combined_balance: false,
+            weekdays: Vec::new(),
+            blackouts: Vec::new(),
+            split_chapters: None,
+            bible_data: None
+        }
+    }
+
+    // Add a track of book indexes to read, e.g. (40..=66).collect() for the New Testament
+    pub fn track(mut self, book_index: Vec<i32>) -> PlanRequest {
+        self.tracks.push(book_index.into_iter().map(Passage::book).collect());
+        self
+    }
+
+    // Add several tracks, read in parallel
+    pub fn tracks(mut self, book_indexes: Vec<Vec<i32>>) -> PlanRequest {
+        for book_index in book_indexes {
+            self = self.track(book_index);
+        }
+        self
+    }
+
+    // Add a track of passages to read in order, e.g. chronological_order(). A book may be read in
+    // several passages with other books in between
+    pub fn passage_track(mut self, passages: Vec<Passage>) -> PlanRequest {
+        self.tracks.push(passages);
+        self
+    }
+
+    // Add several tracks of passages, read in parallel
+    pub fn passage_tracks(mut self, tracks: Vec<Vec<Passage>>) -> PlanRequest {
+        self.tracks.extend(tracks);
+        self
+    }
+
+    // Set the last day of the reading, replacing any day count or daily length
+    pub fn end_date(mut self, end_date: NaiveDate) -> PlanRequest {
+        self.end_date = Some(end_date);
+        self.days = None;
+        self.daily_length = None;
+        self
+    }
+
+    // Set the total number of days, replacing any end date or daily length
+    pub fn days(mut self, days: i32) -> PlanRequest {
+        self.days = Some(days);
+        self.end_date = None;
+        self.daily_length = None;
+        self
+    }
+
+    // Aim for about `length` characters a day across all tracks, replacing any end date or day
+    // count. The number of days is the total length divided by `length`, but no more than every
+    // track can fill with at least a chapter a day, so a short length may not be reached
+    pub fn daily_length(mut self, length: i32) -> PlanRequest {
+        self.daily_length = Some(length);
+        self.end_date = None;
+        self.days = None;
+        self
+    }
+
+    // Set the method used to split each book's chapters across its days
+    pub fn algorithm(mut self, algorithm: Algorithm) -> PlanRequest {
+        self.algorithm = algorithm;
+        self
+    }
+
+    // Set whether days are allocated book by book or across the whole track
+    pub fn balance(mut self, balance: Balance) -> PlanRequest {
+        self.balance = balance;
+        self
+    }
+
+    // With track balancing, set whether a day may read the end of one book and the start of the next
+    pub fn allow_spanning(mut self, allow_spanning: bool) -> PlanRequest {
+        self.allow_spanning = allow_spanning;
+        self
+    }
+
+    // Set where catch-up days are placed. Every policy but CatchUp::Auto places exactly its own
+    // catch-up days and spreads the readings over all the other days
+    pub fn catch_up(mut self, catch_up: CatchUp) -> PlanRequest {
+        self.catch_up = catch_up;
+        self
+    }
+
+    // Set whether the tracks share their catch-up days. With CatchUpSync::Joint, the catch-up days of
+    // CatchUp::Auto or a boundary policy are those of the track with the fewest, and every track
+    // reads on all the other days. Calendar policies already give every track the same days
+    pub fn catch_up_sync(mut self, catch_up_sync: CatchUpSync) -> PlanRequest {
+        self.catch_up_sync = catch_up_sync;
+        self
+    }
+
+    // With several tracks, set whether to even out the daily length of all tracks together rather
+    // than of each track on its own, by moving chapters between the days of a book in one track to
+    // make up for long or short days in the others
+    pub fn combined_balance(mut self, combined_balance: bool) -> PlanRequest {
+        self.combined_balance = combined_balance;
+        self
+    }
+
+    // Read only on these days of the week, e.g. Monday to Friday. With a day count, the count
+    // is of reading days. An empty list reads every day
+    pub fn weekdays(mut self, weekdays: Vec<Weekday>) -> PlanRequest {
+        self.weekdays = weekdays;
+        self
+    }
+
+    // Skip the dates from `first` to `last` inclusive, e.g. a holiday or a retreat. The readings
+    // are moved onto the remaining days
+    pub fn blackout(mut self, first: NaiveDate, last: NaiveDate) -> PlanRequest {
+        self.blackouts.push((first, last));
+        self
+    }
+
+    // Skip several blackout date ranges
+    pub fn blackouts(mut self, blackouts: Vec<(NaiveDate, NaiveDate)>) -> PlanRequest {
+        self.blackouts.extend(blackouts);
+        self
+    }
+
+    // Split chapters longer than `fraction` of a track's average daily length at verse boundaries,
+    // using the verse lengths in `verse_data`, so that a long chapter such as Psalm 119 is read over
+    // several days. Readings that start or end within a chapter give their verses
+    pub fn split_chapters(mut self, verse_data: VerseData, fraction: f64) -> PlanRequest {
+        self.split_chapters = Some((verse_data, fraction));
+        self
+    }
+
+    // Use chapter data loaded with BibleData::load instead of the built-in data
+    pub fn bible_data(mut self, bible_data: BibleData) -> PlanRequest {
+        self.bible_data = Some(bible_data);
+        self
+    }
+
+    // Plan each track, combine the plans by day and sum the daily reading lengths
+    pub fn generate(&self) -> Result<Plan, PlannerError> {
+        match (self.days, self.end_date, self.daily_length) {
+            (Some(days), _, _) if days <= 0 => return Err(PlannerError::InvalidDuration(days)),
+            (None, Some(end_date), _) if end_date <= self.start_date =>
+                return Err(PlannerError::InvalidDates { start: self.start_date, end: end_date }),
+            (None, None, Some(length)) if length <= 0 => return Err(PlannerError::InvalidDailyLength(length)),
+            (None, None, None) => return Err(PlannerError::MissingEndDate),
+            _ => {}
+        }
+        if self.tracks.is_empty() {
+            return Err(PlannerError::NoBooksSelected);
+        }
+        match self.catch_up {
+            CatchUp::Every(0) => return Err(PlannerError::InvalidCatchUp("the catch-up interval must be greater than zero".to_string())),
+            CatchUp::Count(0) => return Err(PlannerError::InvalidCatchUp("the number of catch-up days must be greater than zero".to_string())),
+            _ => {}
+        }
+        if let Some((_, fraction)) = self.split_chapters.as_ref().filter(|(_, fraction)| fraction.is_nan() || *fraction <= 0.0) {
+            return Err(PlannerError::InvalidSplitFraction(*fraction));
+        }
+
+        // Load the built-in chapter data once for all tracks if none was given
+        let builtin_data: BibleData;
+        let bible: &BibleData = match &self.bible_data {
+            Some(bible_data) => bible_data,
+            None => {
+                builtin_data = BibleData::load(None)?;
+                &builtin_data
+            }
+        };
+
+        // Plan the passages that read only part of a book as books of their own
+        let passage_books: Option<PassageBooks> = match self.tracks.iter().flatten().any(|passage| passage.chapters.is_some()) {
+            true => Some(PassageBooks::new(bible, &self.tracks)?),
+            false => None
+        };
+        let (book_indexes, bible): (Vec<Vec<i32>>, &BibleData) = match &passage_books {
+            Some(passage_books) => (passage_books.book_indexes.clone(), &passage_books.bible),
+            None => (self.tracks.iter().map(|track| track.iter().map(|passage| passage.index).collect()).collect(), bible)
+        };
+
+        // A daily length sets the number of days
+        let days: Option<i32> = match self.daily_length {
+            Some(length) => Some(self.get_days_for_length(length, &book_indexes, bible)?),
+            None => self.days
+        };
+
+        // Every day but a catch-up day reads at least one chapter, so a day count that leaves more days
+        // than a track has chapters is rejected before searching for that many reading days. Split
+        // chapters add chapters, so such a track is only checked once it is split
+        if let (Some(days), None) = (days, &self.split_chapters) {
+            for book_index in &book_indexes {
+                let bible_data: Vec<ChapterData> = bible.get_bible_data(book_index)?;
+                let chapters: i32 = bible_data.iter().map(|book| book.chapters).sum();
+                let catch_ups = most_catch_up_days(self.catch_up, days, bible_data.len(), &self.weekdays, &self.blackouts);
+                if days as i64 - catch_ups > chapters as i64 {
+                    return Err(PlannerError::TooManyDays { days, chapters });
+                }
+            }
+        }
+
+        // The readings are planned on consecutive days from the start date up to plan_end, and
+        // each of those days is then moved onto the matching reading day
+        let reading_dates: Vec<NaiveDate> = get_reading_dates(self.start_date, self.end_date, days, &self.weekdays, &self.blackouts);
+        let duration = get_duration(&reading_dates);
+        let plan_end = self.start_date + Duration::days(duration as i64);
+        let end_date = match self.end_date {
+            Some(end_date) if days.is_none() => end_date,
+            _ => *reading_dates.last().unwrap_or(&self.start_date)
+        };
+        if reading_dates.is_empty() || days.is_some_and(|days| reading_dates.len() < days as usize) {
+            return Err(PlannerError::NoReadingDays { start: self.start_date, end: end_date });
+        }
+        // A single reading day leaves no room for the catch-up day that CatchUp::Auto keeps at the end
+        let catch_up: CatchUp = if reading_dates.len() == 1 { CatchUp::None } else { self.catch_up };
+        let catch_up_slots: Vec<bool> = get_catch_up_slots(catch_up, &reading_dates);
+        let rest_days: Vec<NaiveDate> = self.start_date.iter_days()
+            .take_while(|date| *date <= end_date)
+            .filter(|date| reading_dates.binary_search(date).is_err())
+            .collect();
+
+        // Split each track's long chapters into parts, each planned as a chapter of its own
+        let mut splits: Vec<Option<SplitBible>> = Vec::new();
+        for book_index in &book_indexes {
+            splits.push(match &self.split_chapters {
+                Some((verse_data, fraction)) => {
+                    let bible_data: Vec<ChapterData> = bible.get_bible_data(book_index)?;
+                    let titles: Vec<String> = bible_data.iter().map(|book| book.title.clone()).collect();
+                    let track_length: f64 = bible_data.iter().map(|book| book.length as f64).sum();
+                    let source = |book: &BookData| passage_books.as_ref().map_or((book.index, 0), |passage_books| passage_books.source(book));
+                    Some(split_long_chapters(bible, &titles, verse_data, source, fraction * track_length / duration.max(1) as f64))
+                }
+                None => None
+            });
+        }
+        let track_bibles: Vec<&BibleData> = splits.iter()
+            .map(|split| split.as_ref().map_or(bible, |(split_bible, _)| split_bible))
+            .collect();
+        let split_titles: Vec<Vec<String>> = splits.iter()
+            .map(|split| split.as_ref().map_or(Vec::new(), |(_, parts)| parts.keys().cloned().collect()))
+            .collect();
+
+        // Book and testament ends are those of the books the passages were taken from
+        let book_end = |book: &BookData| passage_books.as_ref().map_or((book.index, true), |passage_books| passage_books.book_end(book));
+
+        // Plan each track on consecutive days from the start date
+        let mut track_plans: Vec<Vec<ChaptersDate>> = Vec::new();
+        for ((book_index, track_bible), split_titles) in book_indexes.iter().zip(&track_bibles).zip(&split_titles) {
+            // Get Bible data for the selected indexes
+            let bible_data: Vec<ChapterData> = track_bible.get_bible_data(book_index)?;
+
+            track_plans.push(if catch_up == CatchUp::Auto {
+                // Assign books and chapters to dates, then adjust dates and fill in catch-up days
+                let titles_chapters_date: Vec<ChaptersDate> = self.plan_track(bible_data, track_bible, split_titles, duration, false, &book_end)?;
+                adjust_dates(titles_chapters_date, track_bible, plan_end)?
+            } else {
+                self.plan_track_with_catch_ups(bible_data, track_bible, split_titles, catch_up, &catch_up_slots, end_date, &book_end)?
+            });
+        }
+
+        // To share the catch-up days that depend on each track's books, plan every track again around
+        // the catch-up days chosen from all of them, placed like those of a calendar policy
+        let depends_on_track = catch_up == CatchUp::Auto || catch_up.is_boundary();
+        if self.catch_up_sync == CatchUpSync::Joint && depends_on_track && track_plans.len() > 1 {
+            let joint_slots: Vec<bool> = get_joint_catch_up_slots(&track_plans, self.start_date, reading_dates.len());
+            for (((track_plan, book_index), track_bible), split_titles) in track_plans.iter_mut().zip(&book_indexes).zip(&track_bibles).zip(&split_titles) {
+                let bible_data: Vec<ChapterData> = track_bible.get_bible_data(book_index)?;
+                *track_plan = self.plan_track_with_catch_ups(bible_data, track_bible, split_titles, CatchUp::None, &joint_slots, end_date, &book_end)?;
+            }
+        }
+
+        if self.combined_balance && track_plans.len() > 1 {
+            balance_combined_lengths(&mut track_plans, &track_bibles, self.start_date);
+        }
+
+        let mut tracks: Vec<Vec<ChaptersDate>> = Vec::new();
+        let mut combined_plans_map: BTreeMap<NaiveDate, Vec<ChaptersDate>> = BTreeMap::new();
+
+        // The schedule is keyed by date, so tracks whose readings fall on different dates still line up,
+        // starting with the rest days on which no track reads
+        let track_count: usize = book_indexes.len();
+        let empty_day = |date: NaiveDate| ScheduleDay { date, tracks: vec![TrackDay::Rest; track_count], readings: Vec::new(), length: 0 };
+        let mut schedule_map: BTreeMap<NaiveDate, ScheduleDay> = rest_days.iter()
+            .map(|date| (*date, empty_day(*date)))
+            .collect();
+
+        for (track, (mut adjusted_plan, split)) in track_plans.into_iter().zip(&splits).enumerate() {
+            let track_bible: &BibleData = track_bibles[track];
+
+            // Move each day onto its reading day
+            for chapters_date in adjusted_plan.iter_mut() {
+                let position = (chapters_date.date - self.start_date).num_days() as usize;
+                chapters_date.date = *reading_dates.get(position)
+                    .ok_or(PlannerError::DateOverflow { date: chapters_date.date, end: plan_end })?;
+            }
+
+            // Find the chapter ranges and the daily reading lengths
+            let mut readings: Vec<(NaiveDate, Option<Reading>)> = get_readings(&adjusted_plan, track + 1, track_bible);
+            let reading_lengths: Vec<DailyLength> = get_daily_reading_lengths(&adjusted_plan, track_bible);
+            if let Some((_, parts)) = &split {
+                join_parts(&mut adjusted_plan, &mut readings, parts, bible);
+            }
+
+            // Combine this adjusted plan into the combined_plans by date, since a day may have
+            // more than one element when it reads several books
+            for chapter_date in &adjusted_plan {
+                combined_plans_map.entry(chapter_date.date).or_default().push(chapter_date.clone());
+            }
+
+            // Add the chapter ranges of this track to the schedule, marking its catch-up days
+            for (date, reading) in readings {
+                let day = schedule_map.entry(date).or_insert_with(|| empty_day(date));
+                match reading {
+                    Some(reading) => {
+                        day.tracks[track] = TrackDay::Reading;
+                        day.readings.push(reading);
+                    }
+                    None if day.tracks[track] == TrackDay::Rest => day.tracks[track] = TrackDay::CatchUp,
+                    None => {}
+                }
+            }
+
+            // Combine the reading lengths
+            for daily in reading_lengths.into_iter() {
+                schedule_map.entry(daily.date).or_insert_with(|| empty_day(daily.date)).length += daily.length;
+            }
+
+            tracks.push(adjusted_plan);
+        }
+
+        // Take the readings and lengths of the reading days from the schedule, so they stay aligned by date
+        let schedule: Vec<ScheduleDay> = schedule_map.into_values().collect();
+        let reading_days: Vec<&ScheduleDay> = schedule.iter()
+            .filter(|day| rest_days.binary_search(&day.date).is_err())
+            .collect();
+        let readings: Vec<Vec<Reading>> = reading_days.iter().map(|day| day.readings.clone()).collect();
+        let lengths: Vec<DailyLength> = reading_days.iter()
+            .map(|day| DailyLength { date: day.date, length: day.length })
+            .collect();
+
+        let mut plan = Plan {
+            start_date: self.start_date,
+            end_date,
+            duration: reading_days.len() as i32,
+            rest_days,
+            tracks,
+            days: combined_plans_map.into_values().collect(),
+            readings,
+            lengths,
+            schedule
+        };
+        if let Some(passage_books) = &passage_books {
+            passage_books.restore(&mut plan);
+        }
+        Ok(plan)
+    }
+
+    // Find the number of days that gives each day about `length` characters across all tracks. A track
+    // can't have more days than chapters. With book balancing and CatchUp::Auto, a book isn't given more
+    // days than chapters either, and the days it can't take are left over as catch-up days, so the
+    // reading days are longer. Days are then added until the mean length of the reading days comes down
+    // to what it would be without that limit, or until the tracks have no more chapters to spread
+    fn get_days_for_length(&self, length: i32, book_indexes: &[Vec<i32>], bible: &BibleData) -> Result<i32, PlannerError> {
+        let mut tracks: Vec<Vec<ChapterData>> = Vec::new();
+        for book_index in book_indexes {
+            tracks.push(bible.get_bible_data(book_index)?);
+        }
+        let track_length = |bible_data: &[ChapterData]| bible_data.iter().map(|book| book.length as f64).sum::<f64>().max(1.0);
+        let total_length: f64 = tracks.iter().map(|bible_data| track_length(bible_data)).sum();
+        let most_days: f64 = tracks.iter()
+            .map(|bible_data| bible_data.iter().map(|book| book.chapters as f64).sum())
+            .fold(f64::MAX, f64::min);
+
+        // The number of days a track reads on when planned over `days` days
+        let reading_days = |bible_data: &[ChapterData], days: f64| -> f64 {
+            match (self.balance, self.catch_up) {
+                (Balance::Book, CatchUp::Auto) => bible_data.iter()
+                    .map(|book| (book.length as f64 * days / track_length(bible_data)).min(book.chapters as f64))
+                    .sum(),
+                _ => days
+            }
+        };
+        let mean_length = |days: f64| -> f64 {
+            tracks.iter().map(|bible_data| track_length(bible_data) / reading_days(bible_data, days)).sum()
+        };
+
+        let mut days = (total_length / length as f64).round().min(most_days).max(1.0);
+        let target = total_length / days;
+        while days < most_days && mean_length(days) > target * (1.0 + 1e-9) {
+            days += 1.0;
+        }
+        Ok(days as i32)
+    }
+
+    // Assign the books and chapters of a track to `days` consecutive days from the start date. With
+    // `exact`, every day has a reading even where the books' share of the days doesn't round evenly.
+    // Otherwise the days are only trimmed if rounding gives the books more days than there are.
+    // `split_titles` are the books whose long chapters were split into parts
+    fn plan_track(
+        &self,
+        bible_data: Vec<ChapterData>,
+        bible: &BibleData,
+        split_titles: &[String],
+        days: i32,
+        exact: bool,
+        book_end: &BookEnd) -> Result<Vec<ChaptersDate>, PlannerError>
+    {
+        let end = self.start_date + Duration::days(days as i64);
+        match self.balance {
+            Balance::Book => {
+                // Determine a vector of the books to read and the number of days for each
+                let mut titles_chapters_days: Vec<ChaptersDays> = get_books_in_days(bible_data, days, split_titles)?;
+                if exact || titles_chapters_days.iter().map(|books| books.days).sum::<i32>() > days {
+                    fit_books_in_days(&mut titles_chapters_days, days, bible);
+                }
+                get_chapters_dates_by_length(bible, titles_chapters_days, self.start_date, end, self.algorithm)
+            }
+            Balance::Track => get_chapters_dates_by_track(bible_data, bible, days, self.start_date, end, self.allow_spanning, book_end)
+        }
+    }
+
+    // Plan a track for an explicit catch-up policy, with one slot per reading date that is true for
+    // a catch-up day. The readings are planned on the days the catch-up days leave. The number of
+    // catch-up days of a boundary policy depends on where the days end, so the track is planned
+    // again until it settles. `book_end` says which books end a book, for a track of passages
+    #[allow(clippy::too_many_arguments)]
+    fn plan_track_with_catch_ups(
+        &self,
+        bible_data: Vec<ChapterData>,
+        bible: &BibleData,
+        split_titles: &[String],
+        catch_up: CatchUp,
+        catch_up_slots: &[bool],
+        end_date: NaiveDate,
+        book_end: &BookEnd) -> Result<Vec<ChaptersDate>, PlannerError>
+    {
+        let mut catch_ups: usize = if catch_up.is_boundary() {
+            // Start from one catch-up day between each pair of books
+            let books = bible_data.iter()
+                .filter(|book| bible.book_by_title(&book.title).is_none_or(|book| book_end(book).1))
+                .count();
+            books.saturating_sub(1).min(catch_up_slots.len() - 1)
+        } else {
+            catch_up_slots.iter().filter(|&&is_catch_up| is_catch_up).count()
+        };
+
+        let mut reading_days: Vec<ChaptersDate> = Vec::new();
+        for _ in 0..5 {
+            let days = catch_up_slots.len().saturating_sub(catch_ups);
+            if days == 0 {
+                return Err(PlannerError::NoReadingDays { start: self.start_date, end: end_date });
+            }
+            reading_days = self.plan_track(bible_data.clone(), bible, split_titles, days as i32, true, book_end)?;
+            if !catch_up.is_boundary() {
+                break;
+            }
+            let boundaries = count_boundaries(&reading_days, bible, catch_up, book_end).min(catch_up_slots.len() - 1);
+            if boundaries == catch_ups {
+                break;
+            }
+            catch_ups = boundaries;
+        }
+
+        place_catch_ups(&reading_days, bible, self.start_date, catch_up, catch_up_slots, book_end)
+    }
+}
+
+// Find the chapter range and length of each reading in a track. A repeated book starts again from
+// chapter 1 once its previous reading reached the last chapter
+fn get_readings(adjusted_plan: &[ChaptersDate], track: usize, bible: &BibleData) -> Vec<(NaiveDate, Option<Reading>)> {
+    // The last chapter read of each book
+    let mut last_chapters: HashMap<String, i32> = HashMap::new();
+
+    adjusted_plan.iter()
+        .map(|chapters_date| {
+            if chapters_date.titles.iter().all(|title| title == "Catch-up day") {
+                return (chapters_date.date, None);
+            }
+
+            let titles = chapters_date.titles.clone();
+            let end_chapter = chapters_date.chapters;
+            let (start_chapter, length) = if titles.len() > 1 {
+                // Several short books read in full
+                let length = titles.iter()
+                    .filter_map(|title| bible.book_by_title(title))
+                    .map(|book| book.length())
+                    .sum();
+                (1, length)
+            } else {
+                let last_chapter = last_chapters.entry(titles[0].clone()).or_insert(0);
+                let start_chapter = if *last_chapter + 1 > end_chapter { 1 } else { *last_chapter + 1 };
+                *last_chapter = end_chapter;
+                let length = (start_chapter..=end_chapter)
+                    .filter_map(|chapter| bible.chapter_length(&titles[0], chapter))
+                    .sum();
+                (start_chapter, length)
+            };
+
+            (chapters_date.date, Some(Reading { track, titles, start_chapter, end_chapter, start_verse: None, end_verse: None, length }))
+        })
+        .collect()
+}
+
+// Turn the chapter numbers of a track planned with split chapters, which count each part of a
+// chapter as a chapter, back into chapters and verses
+fn join_parts(
+    adjusted_plan: &mut [ChaptersDate],
+    readings: &mut [(NaiveDate, Option<Reading>)],
+    parts: &HashMap<String, Vec<ChapterPart>>,
+    bible: &BibleData)
+{
+    let part = |title: &str, chapter: i32| parts.get(title).and_then(|parts| parts.get(chapter as usize - 1));
+    let total_chapters = |titles: &[String]| titles.iter()
+        .filter_map(|title| bible.book_by_title(title))
+        .map(|book| book.chapters())
+        .sum::<i32>();
+
+    for chapters_date in adjusted_plan.iter_mut() {
+        if chapters_date.titles.len() > 1 {
+            chapters_date.chapters = total_chapters(&chapters_date.titles);
+        } else if let Some(part) = part(&chapters_date.titles[0], chapters_date.chapters) {
+            chapters_date.chapters = part.chapter;
+        }
+    }
+
+    for reading in readings.iter_mut().filter_map(|(_, reading)| reading.as_mut()) {
+        if reading.titles.len() > 1 {
+            reading.end_chapter = total_chapters(&reading.titles);
+        } else if let (Some(first), Some(last)) = (part(&reading.titles[0], reading.start_chapter), part(&reading.titles[0], reading.end_chapter)) {
+            // Give the verses only if the reading starts or ends within a chapter
+            let starts_within = first.verses.is_some_and(|(verse, _)| verse > 1);
+            let ends_within = part(&reading.titles[0], reading.end_chapter + 1).is_some_and(|next| next.chapter == last.chapter);
+            reading.start_chapter = first.chapter;
+            reading.end_chapter = last.chapter;
+            if starts_within || ends_within {
+                reading.start_verse = first.verses.map(|(verse, _)| verse);
+                reading.end_verse = last.verses.map(|(_, verse)| verse);
+            }
+        }
+    }
+}
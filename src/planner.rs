@@ -0,0 +1,1233 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use chrono::{ Duration, NaiveDate, Weekday };
+use serde::{ Deserialize, Serialize };
+use crate::data::{ BibleData, BookData, ChapterData, VerseData };
+use crate::error::PlannerError;
+
+#[derive(Debug, Deserialize, Clone)]
+pub(crate) struct ChaptersDays {
+    pub titles: Vec<String>,
+    pub chapters: i32,
+    pub days: i32,
+    // The fewest days the books may be given when the days are fitted to the plan
+    pub min_days: i32
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ChaptersDate {
+    pub titles: Vec<String>,
+    pub chapters: i32,
+    pub date: NaiveDate
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DailyLength {
+    pub date: NaiveDate,
+    pub length: i32
+}
+
+// What the planner reads as one chapter of a book whose long chapters are split: a whole chapter,
+// or a range of verses of a long one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ChapterPart {
+    pub chapter: i32,
+    // The first and last verse of part of a chapter
+    pub verses: Option<(i32, i32)>
+}
+
+// Chapter data with long chapters split into parts, and the parts each book's chapters became
+pub(crate) type SplitBible = (BibleData, HashMap<String, Vec<ChapterPart>>);
+
+// For a book of the planning data, the index of the book it was taken from and whether reading it to
+// its end finishes that book. Only a book made from a passage that stops short of the end differs
+pub(crate) type BookEnd<'a> = dyn Fn(&BookData) -> (i32, bool) + 'a;
+
+// The method used to split a book's chapters across the days assigned to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    // Binary search for a threshold on each day's length relative to the average
+    #[default]
+    Heuristic,
+    // Exact partition minimizing the variance of the daily lengths
+    Optimal
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Algorithm, String> {
+        match s {
+            "heuristic" => Ok(Algorithm::Heuristic),
+            "optimal" => Ok(Algorithm::Optimal),
+            _ => Err(format!("unknown algorithm '{}', expected heuristic or optimal", s))
+        }
+    }
+}
+
+// How days are allocated to the chapters of a track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Balance {
+    // Give each book a whole number of days, then split each book's chapters across its days
+    #[default]
+    Book,
+    // Treat the whole track as one stream of chapters and split it across all days
+    Track
+}
+
+impl FromStr for Balance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Balance, String> {
+        match s {
+            "book" => Ok(Balance::Book),
+            "track" => Ok(Balance::Track),
+            _ => Err(format!("unknown balance '{}', expected book or track", s))
+        }
+    }
+}
+
+// Where catch-up days are placed in a plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub enum CatchUp {
+    // Fill the days left over after planning: one between Malachi and Matthew, one at the end,
+    // then any more spread evenly, after first splitting days that read several books
+    #[default]
+    Auto,
+    // No catch-up days: every day has a reading
+    None,
+    // Every Nth day of the plan
+    Every(u32),
+    // Every given day of the week, e.g. every Sunday
+    Weekly(Weekday),
+    // After each day that finishes a book
+    BookEnd,
+    // After each day that finishes a book where the next day starts the other testament
+    TestamentEnd,
+    // The last day of each month
+    MonthEnd,
+    // A fixed number of catch-up days spread evenly, the last on the final day
+    Count(u32)
+}
+
+impl CatchUp {
+    // Whether the catch-up days depend on where the books end rather than on the calendar
+    pub(crate) fn is_boundary(&self) -> bool {
+        matches!(self, CatchUp::BookEnd | CatchUp::TestamentEnd)
+    }
+}
+
+impl FromStr for CatchUp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<CatchUp, String> {
+        let s = s.trim().to_lowercase();
+        let count = |n: &str| n.trim().parse::<u32>().ok().filter(|&n| n > 0)
+            .ok_or_else(|| format!("invalid catch-up count '{}', expected a positive number", n));
+        match s.as_str() {
+            "auto" => Ok(CatchUp::Auto),
+            "none" => Ok(CatchUp::None),
+            "book" | "book-end" => Ok(CatchUp::BookEnd),
+            "testament" | "testament-end" => Ok(CatchUp::TestamentEnd),
+            "month-end" => Ok(CatchUp::MonthEnd),
+            _ => if let Some(n) = s.strip_prefix("every:") {
+                Ok(CatchUp::Every(count(n)?))
+            } else if let Some(n) = s.strip_prefix("count:") {
+                Ok(CatchUp::Count(count(n)?))
+            } else if let Ok(weekday) = s.parse::<Weekday>() {
+                Ok(CatchUp::Weekly(weekday))
+            } else {
+                Err(format!("unknown catch-up policy '{}', expected auto, none, every:N, a day of the week, \
+                    book, testament, month-end or count:N", s))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for CatchUp {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<CatchUp, String> {
+        s.parse()
+    }
+}
+
+// How the catch-up days of several tracks are chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatchUpSync {
+    // Each track has its own catch-up days, so one track may catch up while another reads
+    #[default]
+    Independent,
+    // All tracks catch up on the same days, and each track's readings are spread over the others
+    Joint
+}
+
+impl FromStr for CatchUpSync {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<CatchUpSync, String> {
+        match s {
+            "independent" => Ok(CatchUpSync::Independent),
+            "joint" => Ok(CatchUpSync::Joint),
+            _ => Err(format!("unknown catch-up sync '{}', expected independent or joint", s))
+        }
+    }
+}
+
+// Find duration in days: the number of reading days after the first one. Without any rest days,
+// this is the number of days from the start date to the end date
+pub fn get_duration(reading_dates: &[NaiveDate]) -> i32 {
+    reading_dates.len() as i32 - 1
+}
+
+// Determine a vector of the books to read and the number of days allocated for each,
+// based on the book indexes and the dates provided. Each element in the returned vector
+// represents a group of books to be read within a single day. A book in `split_titles` has
+// chapters split into parts, and is given the days its length needs rounded up, so that a long
+// chapter is read over several days rather than on a single day for the whole book
+pub(crate) fn get_books_in_days(bible_data: Vec<ChapterData>, duration: i32, split_titles: &[String]) -> Result<Vec<ChaptersDays>, PlannerError> {
+    let mut result = Vec::new();
+
+    // Temporary storage for book titles that will be combined into a single day's reading.
+    let mut temp_titles: Vec<String> = Vec::new();
+    // Accumulators for the total number of chapters from and the total number of days required for the temporary book(s).
+    let mut temp_chapters: i32 = 0;
+    let mut temp_days: f32 = 0.0;
+
+    let total_chapter_count: i32 = bible_data.iter().map(|b| b.chapters).sum();
+    if duration > total_chapter_count {
+        return Err(PlannerError::TooManyDays { days: duration, chapters: total_chapter_count });
+    }
+
+    let total_word_count: i32 = bible_data.iter().map(|b| b.length).sum();
+
+    for book in bible_data {
+        // Number of days needed to read the current book.
+        let days: f32 = (book.length as f32 / total_word_count as f32) * duration as f32;
+        // Combine books for partial days.
+        if days >= 0.66 {
+            // If there are already books scheduled for the current day, finalize the day's schedule and start a new one.
+            if !temp_titles.is_empty() {
+                push_new_element(&mut result, temp_titles, temp_chapters, temp_days, duration, false);
+                temp_titles = Vec::new();
+                temp_chapters = 0;
+                temp_days = 0.0;
+            }
+            let split = split_titles.contains(&book.title);
+            push_new_element(&mut result, vec![book.title], book.chapters, days, duration, split);
+        } else {
+            // If the book fits within the current day, add it to the temporary storage.
+            temp_titles.push(book.title);
+            temp_chapters += book.chapters;
+            temp_days += days;
+            // If the accumulated days for the current day exceed one, finalize the day's schedule and start a new one.
+            if temp_days >= 1.0 {
+                push_new_element(&mut result, temp_titles, temp_chapters, temp_days, duration, false);
+                temp_titles = Vec::new();
+                temp_chapters = 0;
+                temp_days = 0.0;
+            }
+        }
+    }
+    // After iterating through all books, check if any remaining books must be scheduled for the last day.
+    if !temp_titles.is_empty() {
+        push_new_element(&mut result, temp_titles, temp_chapters, temp_days, duration, false);
+    }
+    Ok(result)
+}
+
+// Adjust the days given to each group of books so that they add up to exactly `days`, for catch-up
+// policies that leave no days over. Days are added to the single books with the longest daily reading
+// and taken from those with the shortest, as long as each book keeps at least one chapter a day.
+// Days are only taken below a book's fewest days once no other book has any to spare
+pub(crate) fn fit_books_in_days(titles_chapters_days: &mut [ChaptersDays], days: i32, bible: &BibleData) {
+    let lengths: Vec<f64> = titles_chapters_days.iter()
+        .map(|books| books.titles.iter()
+            .filter_map(|title| bible.book_by_title(title))
+            .map(|book| book.length() as f64)
+            .sum())
+        .collect();
+    let mut total: i32 = titles_chapters_days.iter().map(|books| books.days).sum();
+
+    while total < days {
+        let longest = titles_chapters_days.iter()
+            .enumerate()
+            .filter(|(_, books)| books.titles.len() == 1 && books.days < books.chapters)
+            .max_by(|(a, x), (b, y)| (lengths[*a] / x.days as f64).total_cmp(&(lengths[*b] / y.days as f64)));
+        match longest {
+            Some((i, _)) => titles_chapters_days[i].days += 1,
+            None => break
+        }
+        total += 1;
+    }
+    while total > days {
+        let shortest = titles_chapters_days.iter()
+            .enumerate()
+            .filter(|(_, books)| books.days > 1)
+            .min_by(|(a, x), (b, y)| (x.days <= x.min_days).cmp(&(y.days <= y.min_days))
+                .then((lengths[*a] / x.days as f64).total_cmp(&(lengths[*b] / y.days as f64))));
+        match shortest {
+            Some((i, _)) => titles_chapters_days[i].days -= 1,
+            None => break
+        }
+        total -= 1;
+    }
+}
+
+// Used in function get_books_in_days
+fn push_new_element(result: &mut Vec<ChaptersDays>, titles: Vec<String>, chapters: i32, days: f32, duration: i32, round_up: bool) {
+    // Round up for a book with split chapters, down for a large number of days, otherwise round to the nearest whole.
+    let rdays_threshold = duration as f32 / 30.0;
+    let rounded_days = if round_up {
+        days.ceil() as i32
+    } else if days > rdays_threshold {
+        days.floor() as i32
+    } else {
+        days.round() as i32
+    };
+
+    // Ensure that rounded_days is at least 1 and no more than the book's chapters, and that a group of
+    // several books is read within a single day
+    let rounded_days = if titles.len() > 1 { 1 } else { rounded_days.min(chapters).max(1) };
+    // A book with split chapters keeps the days it was given
+    let min_days = if round_up { rounded_days } else { 1 };
+
+    let new_element = ChaptersDays { titles, chapters, days: rounded_days, min_days };
+    result.push(new_element);
+}
+
+// Assign books and chapters to dates, taking into account chapter lengths
+pub(crate) fn get_chapters_dates_by_length(bible: &BibleData, titles_chapters_days: Vec<ChaptersDays>, start: NaiveDate, end: NaiveDate, algorithm: Algorithm) -> Result<Vec<ChaptersDate>, PlannerError> {
+    let mut title_chapters_dates: Vec<ChaptersDate> = Vec::new();
+    let mut current_date: NaiveDate = start;
+
+    // Iterate through each set of books and chapters grouped by days
+    for books in titles_chapters_days {
+        if books.chapters < books.days {
+            return Err(PlannerError::TooFewChapters { title: books.titles[0].clone(), chapters: books.chapters, days: books.days });
+        }
+        // If exactly one day is assigned, directly assign the book to the current date.
+        if books.days == 1 {
+            title_chapters_dates.push(ChaptersDate {
+                titles: books.titles,
+                chapters: books.chapters,
+                date: current_date
+            });
+            // Move to the next date and ensure the date does not exceed the end date.
+            current_date = next_date(current_date, end)?;
+            continue;
+        }
+
+        // Load the data for the particular book into chapters
+        let title = &books.titles[0];
+        let chapters: Vec<ChapterData> = bible.get_chapter_data(title);
+
+        // Group the chapters into one set of chapter numbers per day
+        let datasets: Vec<Vec<i32>> = match algorithm {
+            Algorithm::Heuristic => partition_by_tuner(&chapters, books.days)
+                .unwrap_or_else(|| partition_optimal(&chapters, books.days)),
+            Algorithm::Optimal => partition_optimal(&chapters, books.days)
+        };
+
+        // Assign each day's chapters to dates.
+        for dataset in datasets.into_iter() {
+            title_chapters_dates.push(ChaptersDate {
+                titles: books.titles.clone(),
+                chapters: *dataset.last().unwrap(),
+                date: current_date,
+            });
+            current_date = next_date(current_date, end)?;
+        }
+    }
+    Ok(title_chapters_dates)
+}
+
+// Used in function get_chapters_dates_by_length. Group chapters into `days` groups by searching for a
+// threshold (the tuner) on how far below the average daily length a day may end. Returns None if no
+// tuner value produces exactly `days` groups
+fn partition_by_tuner(chapters: &[ChapterData], days: i32) -> Option<Vec<Vec<i32>>> {
+    let book_days: f64 = days as f64;
+    let total_words: f64 = chapters.iter().map(|chapter| chapter.length as f64).sum();
+    let average_words_per_day: f64 = total_words / book_days;
+
+    // Perform binary search to find the optimal distribution of chapters across days.
+    // The search interval stops shrinking after about 60 halvings, so give up after that.
+    let mut low = 0.0;
+    let mut high = 1.0;
+    let mut tuner = 0.0;
+    for _ in 0..64 {
+        // Group chapters based on the average words per day.
+        let mut datasets: Vec<Vec<i32>> = Vec::new();
+        let mut current_group_total_words: f64 = 0.0;
+        let mut chapter_numbers: Vec<i32> = Vec::new();
+
+        for chapter in chapters {
+            current_group_total_words += chapter.length as f64;
+            chapter_numbers.push(chapter.chapters);
+
+            // Continue if the current group's word count exceeds the average.
+            if (average_words_per_day - current_group_total_words) / average_words_per_day > tuner {
+                continue;
+            } else {
+                datasets.push(chapter_numbers.clone());
+                current_group_total_words = 0.0;
+                chapter_numbers.clear();
+            }
+        }
+
+        // Add any remaining chapters to the last dataset.
+        if !chapter_numbers.is_empty() {
+            datasets.push(chapter_numbers.clone());
+        }
+
+        // Stop when the number of datasets matches the number of days.
+        if (datasets.len() as f64) == book_days {
+            return Some(datasets);
+        } else if (datasets.len() as f64) < book_days {
+            low = tuner;
+        } else {
+            high = tuner;
+        }
+        tuner = (low + high) / 2.0;
+    }
+    None
+}
+
+// Used in function get_chapters_dates_by_length. Split the chapters, in order, into exactly `days`
+// non-empty groups, minimizing the sum of the squared daily lengths. Since the total length and
+// number of days are fixed, this minimizes the variance of the daily lengths. Where the tuner finds
+// a split, no day may be longer than its longest day, so the result is never worse by either measure
+fn partition_optimal(chapters: &[ChapterData], days: i32) -> Vec<Vec<i32>> {
+    let n = chapters.len();
+    let days = (days.max(1) as usize).min(n);
+
+    // prefix[i] is the total length of the first i chapters
+    let mut prefix: Vec<i64> = vec![0; n + 1];
+    for (i, chapter) in chapters.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + chapter.length as i64;
+    }
+    // The longest day of the tuner's split, if it finds one
+    let longest: i64 = partition_by_tuner(chapters, days as i32)
+        .and_then(|datasets| {
+            let mut read = 0;
+            datasets.iter()
+                .map(|dataset| {
+                    let first = read;
+                    read += dataset.len();
+                    prefix[read] - prefix[first]
+                })
+                .max()
+        })
+        .unwrap_or(i64::MAX);
+
+    // cost[k][i] is the lowest sum of squares for reading the first i chapters in k days,
+    // and split[k][i] is the number of chapters read before the last of those k days.
+    let mut cost: Vec<Vec<i64>> = vec![vec![i64::MAX; n + 1]; days + 1];
+    let mut split: Vec<Vec<usize>> = vec![vec![0; n + 1]; days + 1];
+    cost[0][0] = 0;
+    for k in 1..=days {
+        for i in k..=n - (days - k) {
+            for j in (k - 1)..i {
+                let length = prefix[i] - prefix[j];
+                if cost[k - 1][j] == i64::MAX || length > longest {
+                    continue;
+                }
+                let total = cost[k - 1][j] + length * length;
+                if total < cost[k][i] {
+                    cost[k][i] = total;
+                    split[k][i] = j;
+                }
+            }
+        }
+    }
+
+    // Walk back through the splits to recover each day's chapters
+    let mut datasets: Vec<Vec<i32>> = Vec::new();
+    let mut i = n;
+    for k in (1..=days).rev() {
+        let j = split[k][i];
+        datasets.push(chapters[j..i].iter().map(|chapter| chapter.chapters).collect());
+        i = j;
+    }
+    datasets.reverse();
+    datasets
+}
+
+// Split the chapters of the titled books that are longer than `limit` at verse boundaries, into the
+// fewest parts no longer than `limit` that the verses allow, each as close in length as possible.
+// Chapters without verse lengths are left whole. `source` gives the index a book's verse lengths are
+// listed under and the number of chapters before its first, for a book made from a passage. Returns
+// the chapter data with each part in place of a chapter, and the parts of each book that was split
+pub(crate) fn split_long_chapters(
+    bible: &BibleData,
+    titles: &[String],
+    verse_data: &VerseData,
+    source: impl Fn(&BookData) -> (i32, i32),
+    limit: f64) -> SplitBible
+{
+    let mut books: Vec<BookData> = bible.books().to_vec();
+    let mut parts_by_title: HashMap<String, Vec<ChapterPart>> = HashMap::new();
+
+    for book in books.iter_mut().filter(|book| titles.contains(&book.title)) {
+        let mut parts: Vec<ChapterPart> = Vec::new();
+        let mut lengths: Vec<i32> = Vec::new();
+
+        let (index, offset) = source(book);
+        for (i, &length) in book.chapter_lengths.iter().enumerate() {
+            let chapter = i as i32 + 1;
+            let verse_lengths = verse_data.verse_lengths(index, chapter + offset).filter(|verses| verses.len() > 1);
+            match verse_lengths {
+                Some(verse_lengths) if length as f64 > limit => {
+                    let count = ((length as f64 / limit).ceil() as usize).min(verse_lengths.len());
+                    let verses: Vec<ChapterData> = verse_lengths.iter()
+                        .enumerate()
+                        .map(|(v, &length)| ChapterData { title: book.title.clone(), chapters: v as i32 + 1, length })
+                        .collect();
+                    for group in partition_optimal(&verses, count as i32) {
+                        parts.push(ChapterPart { chapter, verses: Some((group[0], group[group.len() - 1])) });
+                        lengths.push(group.iter().map(|&verse| verse_lengths[verse as usize - 1]).sum());
+                    }
+                }
+                _ => {
+                    parts.push(ChapterPart { chapter, verses: None });
+                    lengths.push(length);
+                }
+            }
+        }
+
+        if parts.len() > book.chapter_lengths.len() {
+            book.chapter_lengths = lengths;
+            parts_by_title.insert(book.title.clone(), parts);
+        }
+    }
+
+    (BibleData::from_books(books), parts_by_title)
+}
+
+// Assign chapters to dates by splitting the whole track, as one stream of chapters, into `duration`
+// days with the lowest variance of daily length. If `allow_spanning` is false, a day may not read the
+// end of one book and the start of the next, although it may still read several whole books.
+// A day reading more than one book has one element per book, all with the same date.
+pub(crate) fn get_chapters_dates_by_track(
+    bible_data: Vec<ChapterData>,
+    bible: &BibleData,
+    duration: i32,
+    start: NaiveDate,
+    end: NaiveDate,
+    allow_spanning: bool,
+    book_end: &BookEnd) -> Result<Vec<ChaptersDate>, PlannerError>
+{
+    // Flatten the track into chapters, remembering where each book starts, and where a book starts
+    // after one that finishes the book it was taken from
+    let mut chapters: Vec<ChapterData> = Vec::new();
+    let mut book_starts: Vec<usize> = Vec::new();
+    let mut whole_book_starts: Vec<usize> = vec![0];
+    for book in &bible_data {
+        book_starts.push(chapters.len());
+        chapters.extend(bible.get_chapter_data(&book.title));
+        if bible.book_by_title(&book.title).is_none_or(|book| book_end(book).1) {
+            whole_book_starts.push(chapters.len());
+        }
+    }
+    book_starts.push(chapters.len());
+    if whole_book_starts.last() != Some(&chapters.len()) {
+        whole_book_starts.push(chapters.len());
+    }
+
+    if duration as usize > chapters.len() {
+        return Err(PlannerError::TooManyDays { days: duration, chapters: chapters.len() as i32 });
+    }
+    let lengths: Vec<i64> = chapters.iter().map(|chapter| chapter.length as i64).collect();
+    let splits: Vec<usize> = if allow_spanning {
+        partition_stream(&lengths, duration as usize)
+    } else {
+        partition_stream_by_books(&lengths, &whole_book_starts, duration as usize)
+            .ok_or(PlannerError::TooManyDays { days: duration, chapters: chapters.len() as i32 })?
+    };
+
+    // Emit one element per book read on each day
+    let mut title_chapters_dates: Vec<ChaptersDate> = Vec::new();
+    let mut current_date: NaiveDate = start;
+    let mut first = 0;
+    for (day, &last) in splits.iter().enumerate() {
+        if day > 0 {
+            current_date = next_date(current_date, end)?;
+        }
+        for (position, chapter) in chapters.iter().enumerate().take(last).skip(first) {
+            // Close off the book at its last chapter or at the end of the day
+            if position + 1 == last || book_starts.contains(&(position + 1)) {
+                title_chapters_dates.push(ChaptersDate {
+                    titles: vec![chapter.title.clone()],
+                    chapters: chapter.chapters,
+                    date: current_date
+                });
+            }
+        }
+        first = last;
+    }
+    next_date(current_date, end)?;
+    Ok(title_chapters_dates)
+}
+
+// Used in function get_chapters_dates_by_track. Split `lengths` into `days` non-empty groups
+// minimizing the sum of squared group lengths, returning the end position of each group. The best
+// split point never moves backwards as a group's end moves forward, so each day's costs are found
+// by divide and conquer in O(n log n) rather than O(n^2).
+fn partition_stream(lengths: &[i64], days: usize) -> Vec<usize> {
+    let n = lengths.len();
+    let mut prefix: Vec<i64> = vec![0; n + 1];
+    for (i, length) in lengths.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + length;
+    }
+
+    // previous[i] is the lowest cost of reading the first i chapters in k - 1 days
+    let mut previous: Vec<i64> = vec![i64::MAX; n + 1];
+    previous[0] = 0;
+    let mut splits: Vec<Vec<usize>> = Vec::with_capacity(days);
+    for k in 1..=days {
+        let mut current: Vec<i64> = vec![i64::MAX; n + 1];
+        let mut split: Vec<usize> = vec![0; n + 1];
+        partition_layer(&prefix, &previous, &mut current, &mut split, k, n - (days - k), k - 1, n - 1);
+        previous = current;
+        splits.push(split);
+    }
+
+    // Walk back through the splits to recover the end of each day
+    let mut ends: Vec<usize> = vec![0; days];
+    let mut i = n;
+    for k in (1..=days).rev() {
+        ends[k - 1] = i;
+        i = splits[k - 1][i];
+    }
+    ends
+}
+
+// Used in function partition_stream. Fill current[low..=high], searching split points in
+// first_split..=last_split
+#[allow(clippy::too_many_arguments)]
+fn partition_layer(prefix: &[i64], previous: &[i64], current: &mut [i64], split: &mut [usize], low: usize, high: usize, first_split: usize, last_split: usize) {
+    if low > high {
+        return;
+    }
+    let middle = (low + high) / 2;
+    let mut best = i64::MAX;
+    let mut best_split = first_split;
+    for j in first_split..=last_split.min(middle - 1) {
+        if previous[j] == i64::MAX {
+            continue;
+        }
+        let length = prefix[middle] - prefix[j];
+        let total = previous[j] + length * length;
+        if total < best {
+            best = total;
+            best_split = j;
+        }
+    }
+    current[middle] = best;
+    split[middle] = best_split;
+
+    if middle > low {
+        partition_layer(prefix, previous, current, split, low, middle - 1, first_split, best_split);
+    }
+    partition_layer(prefix, previous, current, split, middle + 1, high, best_split, last_split);
+}
+
+// Used in function get_chapters_dates_by_track. Like partition_stream, but a group must either stay
+// within one book or start and end on book boundaries. Returns None if no such split exists.
+fn partition_stream_by_books(lengths: &[i64], book_starts: &[usize], days: usize) -> Option<Vec<usize>> {
+    let n = lengths.len();
+    let mut prefix: Vec<i64> = vec![0; n + 1];
+    for (i, length) in lengths.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + length;
+    }
+
+    // book_start[i] is the start of the book containing chapter i - 1, the last chapter before position i
+    let mut book_start: Vec<usize> = vec![0; n + 1];
+    for window in book_starts.windows(2) {
+        book_start[window[0] + 1..=window[1]].fill(window[0]);
+    }
+    let mut boundaries: Vec<usize> = book_starts.to_vec();
+    boundaries.dedup();
+
+    let mut cost: Vec<Vec<i64>> = vec![vec![i64::MAX; n + 1]; days + 1];
+    let mut split: Vec<Vec<usize>> = vec![vec![0; n + 1]; days + 1];
+    cost[0][0] = 0;
+    for k in 1..=days {
+        for i in k..=n - (days - k) {
+            // Groups that end part way through a book must start in the same book
+            let within_book = book_start[i].max(k - 1)..i;
+            // Groups that end on a book boundary may also start on any earlier book boundary
+            let whole_books: &[usize] = if boundaries.binary_search(&i).is_ok() {
+                let first = boundaries.partition_point(|&j| j < k - 1);
+                let last = boundaries.partition_point(|&j| j < book_start[i]).max(first);
+                &boundaries[first..last]
+            } else {
+                &[]
+            };
+            for j in within_book.chain(whole_books.iter().copied()) {
+                if cost[k - 1][j] == i64::MAX {
+                    continue;
+                }
+                let length = prefix[i] - prefix[j];
+                let total = cost[k - 1][j] + length * length;
+                if total < cost[k][i] {
+                    cost[k][i] = total;
+                    split[k][i] = j;
+                }
+            }
+        }
+    }
+    if cost[days][n] == i64::MAX {
+        return None;
+    }
+
+    let mut ends: Vec<usize> = vec![0; days];
+    let mut i = n;
+    for k in (1..=days).rev() {
+        ends[k - 1] = i;
+        i = split[k][i];
+    }
+    Some(ends)
+}
+
+// Used in function get_chapters_dates_by_length
+fn next_date(date: NaiveDate, end: NaiveDate) -> Result<NaiveDate, PlannerError> {
+    match date.succ_opt() {
+        Some(next) if next <= end => Ok(next),
+        Some(next) => Err(PlannerError::DateOverflow { date: next, end }),
+        None => Err(PlannerError::DateOverflow { date, end })
+    }
+}
+
+// Adjust dates, fill in catch-up days, split up combined readings if reasonable
+pub(crate) fn adjust_dates(titles_chapters_date: Vec<ChaptersDate>, bible: &BibleData, end: NaiveDate) -> Result<Vec<ChaptersDate>, PlannerError> {
+    let mut new_tcds: Vec<ChaptersDate> = titles_chapters_date.clone();
+    if new_tcds.is_empty() {
+        return Ok(new_tcds);
+    }
+
+    // find initial number of leftover days
+    let last_date: NaiveDate = if let Some(ChaptersDate { date, .. }) = titles_chapters_date.last() {
+        *date
+    } else { end };
+    let diff = end - last_date;
+    let mut num_days = diff.num_days();
+
+    // Add a catch-up day between the OT and NT if applicable, each time a repeated selection passes
+    // from Malachi to Matthew, for as long as there are leftover days
+    let mut i = 0;
+    while num_days > 0 && i + 1 < new_tcds.len() {
+        let current_titles = &new_tcds[i].titles;
+        let next_titles = &new_tcds[i + 1].titles;
+
+        // Only insert between different days, not between the books of a day spanning both
+        if current_titles.contains(&"Malachi".to_string()) && next_titles.contains(&"Matthew".to_string())
+            && new_tcds[i].date != new_tcds[i + 1].date {
+            insert_new_element(&mut new_tcds, i, "Catch-up day".to_string(), 0);
+            num_days -= 1;
+        }
+        i += 1;
+    }
+
+    // Add a catch-up day at the end of the reading
+    if num_days > 0 {
+        let i = new_tcds.len() - 1;
+        // Insert a new element
+        let new_date = new_tcds[i].date + Duration::days(1);
+        let new_element = ChaptersDate { titles: vec!["Catch-up day".to_string()], chapters: 0, date: new_date };
+        new_tcds.push(new_element);
+
+        num_days -= 1;
+    }
+
+    // Continue adjusting for multiple titles until there are no more leftover days
+    while num_days > 1 {
+        // Find elements with multiple titles that can be split within the leftover days
+        let elements_with_multiple_titles: Vec<_> = new_tcds
+            .iter()
+            .filter(|entry| entry.titles.len() > 1 && entry.titles.len() as i64 - 1 <= num_days)
+            .cloned()
+            .collect();
+
+        // Find the element with the greatest number of chapters among those with multiple titles
+        let max_chapters_element = elements_with_multiple_titles
+            .iter()
+            .max_by_key(|entry| entry.chapters);
+
+        if let Some(max_chapters_element) = max_chapters_element {
+            // Find the index of the element with the greatest chapters
+            if let Some(index) = new_tcds.iter().position(| entry| *entry == *max_chapters_element) {
+                // Split the element into individual elements for each title
+                let titles = max_chapters_element.titles.clone();
+                let num_titles = titles.len() as i32;
+                let date = max_chapters_element.date;
+
+                // Remove the original element
+                new_tcds.remove(index);
+    
+                // Insert new elements for each title with adjusted dates
+                for (i, title) in titles.iter().enumerate() {
+                    let new_date = date + Duration::days(i as i64);
+                    let book = bible.book_by_title(title)
+                        .ok_or_else(|| PlannerError::UnknownBook { name: title.clone(), suggestions: Vec::new() })?;
+                    let new_element = ChaptersDate {
+                        titles: vec![title.clone()],
+                        chapters: book.chapters(),
+                        date: new_date,
+                    };
+                    new_tcds.insert(index + i, new_element);
+                }
+
+                // Adjust subsequent element dates
+                let adj_days = (num_titles - 1) as i64;
+                for entry in new_tcds.iter_mut().skip(index + titles.len()) {
+                    entry.date += Duration::days(adj_days);
+                }
+
+                num_days -= num_titles as i64;
+            } else {
+                // No more elements with multiple titles, break the loop
+                break;
+            }
+        } else {
+            // No more elements with multiple titles, break the loop
+            break;
+        }
+    }
+
+    // If any days remain unassigned, add catch-up days regularly throughout
+    let first_date: NaiveDate = if let Some(ChaptersDate { date, .. }) = new_tcds.first() {
+        *date
+    } else { end };
+    let last_date: NaiveDate = if let Some(ChaptersDate { date, .. }) = new_tcds.last() {
+        *date
+    } else { end };
+    
+    let num_days = (end - last_date).num_days();
+    if num_days > 0 {
+        let dur = (last_date - first_date).num_days();
+        let days_between = (dur / (num_days + 1)) as usize;
+        let mut catchup_day_count = 1;
+
+        for i in 0..new_tcds.len() - 1 {
+            let current_titles = &new_tcds[i].titles;
+            let next_titles = &new_tcds[i + 1].titles;
+    
+            if catchup_day_count <= num_days as usize && i > days_between * catchup_day_count
+                && current_titles != next_titles && new_tcds[i].date != new_tcds[i + 1].date {
+                insert_new_element(&mut new_tcds, i, "Catch-up day".to_string(), 0);
+                 catchup_day_count += 1;
+            }
+        }
+    }
+
+    // Any days still left, e.g. where a repeated book gives no change of title to put them at,
+    // become catch-up days at the end, so that the plan fills every day up to `end`
+    while let Some(last) = new_tcds.last().filter(|last| last.date < end) {
+        let date = last.date + Duration::days(1);
+        new_tcds.push(ChaptersDate { titles: vec!["Catch-up day".to_string()], chapters: 0, date });
+    }
+
+    Ok(new_tcds)
+}
+
+// Even out the combined daily length of several tracks planned on the same consecutive days from
+// `start`. The boundary between two consecutive days of one book in a track moves a chapter at a
+// time, towards the day whose combined length is shorter, while that brings the two days closer
+// together. Each day keeps at least one chapter and each book keeps its days, so one track's days
+// grow where another track's are short
+pub(crate) fn balance_combined_lengths(track_plans: &mut [Vec<ChaptersDate>], bibles: &[&BibleData], start: NaiveDate) {
+    let position = |date: NaiveDate| (date - start).num_days() as usize;
+    let days = track_plans.iter().flatten().map(|chapters_date| position(chapters_date.date) + 1).max().unwrap_or(0);
+
+    let mut combined: Vec<i64> = vec![0; days];
+    for (track_plan, bible) in track_plans.iter().zip(bibles) {
+        for daily in get_daily_reading_lengths(track_plan, bible) {
+            combined[position(daily.date)] += daily.length as i64;
+        }
+    }
+
+    let mut moved = true;
+    while moved {
+        moved = false;
+        for (track_plan, bible) in track_plans.iter_mut().zip(bibles) {
+            for j in 0..track_plan.len().saturating_sub(1) {
+                let (day, next_day) = (&track_plan[j], &track_plan[j + 1]);
+                if day.titles.len() != 1 || day.titles != next_day.titles || day.date == next_day.date
+                    || day.titles[0] == "Catch-up day" {
+                    continue;
+                }
+                // A repeated book starts again on the next day: its chapters belong to another pass
+                if next_day.chapters <= day.chapters {
+                    continue;
+                }
+                let title = &day.titles[0];
+                let Some(book) = bible.book_by_title(title) else { continue };
+
+                // The day's first chapter follows the previous day's last, unless a repeated book starts again
+                let first_chapter = match j.checked_sub(1).map(|i| &track_plan[i]) {
+                    Some(previous) if previous.titles == day.titles && previous.chapters < book.chapters() => previous.chapters + 1,
+                    _ => 1
+                };
+                let (here, there) = (position(day.date), position(next_day.date));
+                let difference = combined[here] - combined[there];
+
+                // Move the day's last chapter to the next day, or the next day's first chapter back
+                let last_length = bible.chapter_length(title, day.chapters).unwrap_or(0) as i64;
+                let next_length = bible.chapter_length(title, day.chapters + 1).unwrap_or(0) as i64;
+                let shift: i64 = if day.chapters > first_chapter && last_length > 0 && difference > last_length {
+                    track_plan[j].chapters -= 1;
+                    last_length
+                } else if day.chapters + 1 < next_day.chapters && next_length > 0 && -difference > next_length {
+                    track_plan[j].chapters += 1;
+                    -next_length
+                } else {
+                    continue;
+                };
+                combined[here] -= shift;
+                combined[there] += shift;
+                moved = true;
+            }
+        }
+    }
+}
+
+// Lay out the days of an explicit catch-up policy: one slot per day from `start`, true for a
+// catch-up day. With a boundary policy, the catch-up days follow the reading days that end a book
+// (or a testament), and the slots only say how many days there are. The readings fill the other
+// slots in order; any slots left over at the end, because the books could not be spread over
+// every reading day, become catch-up days too
+pub(crate) fn place_catch_ups(
+    reading_days: &[ChaptersDate],
+    bible: &BibleData,
+    start: NaiveDate,
+    catch_up: CatchUp,
+    slots: &[bool],
+    book_end: &BookEnd) -> Result<Vec<ChaptersDate>, PlannerError>
+{
+    let slots: Vec<bool> = if catch_up.is_boundary() {
+        get_boundary_slots(reading_days, bible, catch_up, slots.len(), book_end)
+    } else {
+        slots.to_vec()
+    };
+
+    let days: Vec<Vec<ChaptersDate>> = group_by_date(reading_days);
+    let mut result: Vec<ChaptersDate> = Vec::new();
+    let mut next_day = 0;
+    for (i, &is_catch_up) in slots.iter().enumerate() {
+        let date = start + Duration::days(i as i64);
+        if !is_catch_up && next_day < days.len() {
+            result.extend(days[next_day].iter().map(|chapters_date| ChaptersDate { date, ..chapters_date.clone() }));
+            next_day += 1;
+        } else {
+            result.push(ChaptersDate { titles: vec!["Catch-up day".to_string()], chapters: 0, date });
+        }
+    }
+
+    if next_day < days.len() {
+        let end = start + Duration::days(slots.len() as i64 - 1);
+        return Err(PlannerError::DateOverflow { date: end + Duration::days((days.len() - next_day) as i64), end });
+    }
+    Ok(result)
+}
+
+// Choose the catch-up days shared by all tracks from each track's own plan over `total` days from
+// `start`: those of the track with the fewest catch-up days, so that every track can still be spread
+// over the days left. A day with no reading counts as a catch-up day
+pub(crate) fn get_joint_catch_up_slots(track_plans: &[Vec<ChaptersDate>], start: NaiveDate, total: usize) -> Vec<bool> {
+    track_plans.iter()
+        .map(|track_plan| {
+            let mut slots: Vec<bool> = vec![true; total];
+            for chapters_date in track_plan.iter().filter(|chapters_date| chapters_date.titles.iter().any(|title| title != "Catch-up day")) {
+                if let Some(slot) = slots.get_mut((chapters_date.date - start).num_days() as usize) {
+                    *slot = false;
+                }
+            }
+            slots
+        })
+        .min_by_key(|slots| slots.iter().filter(|&&is_catch_up| is_catch_up).count())
+        .unwrap_or_else(|| vec![false; total])
+}
+
+// Find the number of catch-up days a boundary policy puts between the reading days
+pub(crate) fn count_boundaries(reading_days: &[ChaptersDate], bible: &BibleData, catch_up: CatchUp, book_end: &BookEnd) -> usize {
+    get_boundaries(&group_by_date(reading_days), bible, catch_up, book_end).iter().filter(|&&b| b).count()
+}
+
+// Used in function place_catch_ups. Lay out a reading slot for each day, followed by a catch-up slot
+// if the day ends a book (or a testament). If this makes more than `total` slots, the last catch-up
+// slots are dropped; if fewer, reading slots are added at the end
+fn get_boundary_slots(reading_days: &[ChaptersDate], bible: &BibleData, catch_up: CatchUp, total: usize, book_end: &BookEnd) -> Vec<bool> {
+    let mut slots: Vec<bool> = Vec::new();
+    for boundary in get_boundaries(&group_by_date(reading_days), bible, catch_up, book_end) {
+        slots.push(false);
+        if boundary {
+            slots.push(true);
+        }
+    }
+
+    while slots.len() > total {
+        match slots.iter().rposition(|&is_catch_up| is_catch_up) {
+            Some(position) => { slots.remove(position); }
+            None => break
+        }
+    }
+    slots.resize(total.max(slots.len()), false);
+    slots
+}
+
+// Used in function place_catch_ups. For each day, whether a catch-up day follows it under a boundary
+// policy. No catch-up day follows the last day. A book made from a passage only ends a book, and is
+// only in the testament of the book it was taken from, as `book_end` gives
+fn get_boundaries(days: &[Vec<ChaptersDate>], bible: &BibleData, catch_up: CatchUp, book_end: &BookEnd) -> Vec<bool> {
+    let is_new_testament = |title: &str| bible.book_by_title(title).map(|book| book_end(book).0 >= 40);
+
+    days.iter()
+        .enumerate()
+        .map(|(i, day)| {
+            let Some(next_day) = days.get(i + 1) else { return false };
+            let last = &day[day.len() - 1];
+            // Several short books are read in full, so the day ends the last of them
+            let ends_book = bible.book_by_title(&last.titles[last.titles.len() - 1])
+                .is_some_and(|book| (last.titles.len() > 1 || book.chapters() == last.chapters) && book_end(book).1);
+            match catch_up {
+                CatchUp::BookEnd => ends_book,
+                CatchUp::TestamentEnd => ends_book
+                    && is_new_testament(&last.titles[last.titles.len() - 1]) != is_new_testament(&next_day[0].titles[0]),
+                _ => false
+            }
+        })
+        .collect()
+}
+
+// Used in function place_catch_ups. Split the readings into days, each with the elements of one date
+fn group_by_date(reading_days: &[ChaptersDate]) -> Vec<Vec<ChaptersDate>> {
+    let mut days: Vec<Vec<ChaptersDate>> = Vec::new();
+    for chapters_date in reading_days {
+        match days.last_mut() {
+            Some(day) if day[0].date == chapters_date.date => day.push(chapters_date.clone()),
+            _ => days.push(vec![chapters_date.clone()])
+        }
+    }
+    days
+}
+
+// Used in the adjust_dates function
+fn insert_new_element(new_tcds: &mut Vec<ChaptersDate>, i: usize, title: String, chapters: i32) {
+    // Insert a new element
+    let new_date = new_tcds[i + 1].date;
+    let new_element = ChaptersDate { 
+        titles: vec![title], 
+        chapters,
+        date: new_date 
+    };
+    new_tcds.insert(i + 1, new_element);
+
+    // Adjust subsequent element dates by one day
+    for entry in new_tcds.iter_mut().skip(i + 2) {
+        entry.date += Duration::days(1);
+    }
+}
+
+pub(crate) fn get_daily_reading_lengths(adjusted_plan: &[ChaptersDate], bible: &BibleData) -> Vec<DailyLength> {
+    let mut result: Vec<DailyLength> = Vec::new();
+
+    let mut prev_end_chapter = 0;
+    let mut prev_title: String = String::new();
+
+    for day in adjusted_plan {
+        let mut total_length = 0;
+
+        // A catch-up day may fall within a book, so it doesn't reset the chapter count
+        if day.titles.iter().all(|title| title == "Catch-up day") {
+            result.push(DailyLength { date: day.date, length: 0 });
+            continue;
+        }
+
+        for title in &day.titles {
+            // A book read again, e.g. in "Psalms x2", restarts from chapter 1
+            let end_chapter = day.chapters;
+            let start_chapter = if prev_title != *title || prev_end_chapter + 1 > end_chapter { 1 } else { prev_end_chapter + 1 };
+
+            // Collect lengths
+            for chapter in start_chapter..=end_chapter {
+                if let Some(length) = bible.chapter_length(title, chapter) {
+                    total_length += length;
+                }
+            }
+
+            prev_end_chapter = end_chapter;
+            prev_title = title.clone();
+        }
+
+        result.push(DailyLength{ date: day.date, length: total_length});
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::VerseData;
+    use crate::passage::chronological_order;
+    use crate::plan::PlanRequest;
+
+    // Books of different sizes with the day counts to split them into, including a short book read
+    // one chapter a day and Psalms in 134 days, which the tuner can't split
+    const CASES: [(&str, &[i32]); 5] = [
+        ("Psalms", &[1, 30, 73, 100, 134, 149, 150]),
+        ("Genesis", &[1, 7, 25, 49, 50]),
+        ("Proverbs", &[3, 10, 20, 31]),
+        ("Ruth", &[1, 2, 4]),
+        ("Jude", &[1])
+    ];
+
+    // The length of each day of a split, given by the chapter numbers read on each day
+    fn daily_lengths(chapters: &[ChapterData], datasets: &[Vec<i32>]) -> Vec<i64> {
+        datasets.iter()
+            .map(|dataset| dataset.iter().map(|&chapter| chapters[chapter as usize - 1].length as i64).sum())
+            .collect()
+    }
+
+    #[test]
+    fn optimal_partition_reads_every_chapter_in_order() {
+        let bible = BibleData::load(None).unwrap();
+        for (title, all_days) in CASES {
+            let chapters: Vec<ChapterData> = bible.get_chapter_data(title);
+            for &days in all_days {
+                let datasets = partition_optimal(&chapters, days);
+                assert_eq!(datasets.len(), days as usize, "{} in {} days", title, days);
+                assert!(datasets.iter().all(|dataset| !dataset.is_empty()), "{} in {} days", title, days);
+                let read: Vec<i32> = datasets.concat();
+                assert_eq!(read, (1..=chapters.len() as i32).collect::<Vec<i32>>(), "{} in {} days", title, days);
+            }
+        }
+    }
+
+    #[test]
+    fn optimal_partition_splits_where_tuner_cannot() {
+        let bible = BibleData::load(None).unwrap();
+        let chapters: Vec<ChapterData> = bible.get_chapter_data("Psalms");
+        assert!(partition_by_tuner(&chapters, 134).is_none());
+
+        let datasets = partition_optimal(&chapters, 134);
+        assert_eq!(datasets.len(), 134);
+        assert_eq!(datasets.concat(), (1..=150).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn optimal_partition_is_no_worse_than_tuner() {
+        let bible = BibleData::load(None).unwrap();
+        for title in ["Genesis", "Proverbs", "Matthew", "Hosea", "Psalms"] {
+            let chapters: Vec<ChapterData> = bible.get_chapter_data(title);
+            for days in 1..=chapters.len() as i32 {
+                let Some(tuner) = partition_by_tuner(&chapters, days) else { continue };
+                let optimal = daily_lengths(&chapters, &partition_optimal(&chapters, days));
+                let tuner = daily_lengths(&chapters, &tuner);
+                assert!(optimal.iter().max() <= tuner.iter().max(), "longest day of {} in {} days", title, days);
+                assert!(optimal.iter().map(|length| length * length).sum::<i64>() <= tuner.iter().map(|length| length * length).sum::<i64>(),
+                    "sum of squares of {} in {} days", title, days);
+            }
+        }
+    }
+
+    #[test]
+    fn auto_catch_up_days_stay_within_repeated_testaments() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let bible: Vec<i32> = (1..=66).collect();
+        for (track, days) in [(bible.repeat(2), 730), (bible.repeat(2), 800), (bible.repeat(3), 1100), ([39, 40].repeat(2), 40)] {
+            let plan = PlanRequest::new(start).track(track).days(days).generate().unwrap();
+            assert_eq!(plan.schedule.len(), days as usize, "{} days", days);
+            assert!(plan.end_date < start + Duration::days(days as i64));
+        }
+    }
+
+    #[test]
+    fn day_count_gives_exactly_that_many_days() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let cases: [(Vec<i32>, i32); 9] = [
+            (vec![1], 50), (vec![8], 1), (vec![8], 3), (vec![8], 4), (vec![19], 151),
+            (vec![8; 4], 16), ((40..=66).collect(), 25), ((40..=66).collect(), 90), ((1..=66).collect(), 365)
+        ];
+        for (track, days) in cases {
+            for catch_up in [CatchUp::Auto, CatchUp::None, CatchUp::Every(7)] {
+                // Without catch-up days, Psalms can't have more days than chapters
+                let plan = match PlanRequest::new(start).track(track.clone()).days(days).catch_up(catch_up).generate() {
+                    Err(PlannerError::TooManyDays { .. }) if catch_up == CatchUp::None => continue,
+                    result => result.unwrap()
+                };
+                assert_eq!(plan.schedule.len(), days as usize, "{:?} in {} days with {:?}", track, days, catch_up);
+                assert_eq!(plan.duration, days, "{:?} in {} days with {:?}", track, days, catch_up);
+                assert_eq!(plan.end_date, start + Duration::days(days as i64 - 1));
+            }
+        }
+    }
+
+    #[test]
+    fn combined_balance_reads_every_chapter_once_per_repeat() {
+        let bible = BibleData::load(None).unwrap();
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let tracks: Vec<Vec<i32>> = vec![vec![19, 19], (40..=66).collect()];
+        for days in [40, 60, 90] {
+            let plan = PlanRequest::new(start).tracks(tracks.clone()).days(days).combined_balance(true).generate().unwrap();
+            for (number, track) in tracks.iter().enumerate() {
+                let expected: Vec<(String, i32)> = track.iter()
+                    .map(|&index| bible.book(index).unwrap())
+                    .flat_map(|book| (1..=book.chapters()).map(|chapter| (book.title.clone(), chapter)))
+                    .collect();
+                let read: Vec<(String, i32)> = plan.schedule.iter()
+                    .flat_map(|day| day.readings.iter().filter(|reading| reading.track == number + 1))
+                    .flat_map(|reading| reading.titles.iter().flat_map(|title| {
+                        let (first, last) = if reading.titles.len() == 1 {
+                            (reading.start_chapter, reading.end_chapter)
+                        } else {
+                            (1, bible.book_by_title(title).unwrap().chapters())
+                        };
+                        (first..=last).map(|chapter| (title.clone(), chapter))
+                    }))
+                    .collect();
+                assert_eq!(read, expected, "track {} in {} days", number + 1, days);
+            }
+        }
+    }
+
+    #[test]
+    fn split_chapter_of_a_one_day_book_is_read_over_several_days() {
+        // In the chronological order Psalm 119 is a book of its own, given one day by book balance
+        let bible = BibleData::load(None).unwrap();
+        let length = bible.chapter_length("Psalms", 119).unwrap();
+        let verses: String = (1..=176).map(|verse| format!("19,Psalms,119,{},{}\n", verse, length / 176)).collect();
+        let verse_data = VerseData::from_reader(format!("index,title,chapter,verse,length\n{}", verses).as_bytes()).unwrap();
+
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let plan = PlanRequest::new(start)
+            .passage_track(chronological_order().unwrap())
+            .end_date(NaiveDate::from_ymd_opt(2025, 12, 31).unwrap())
+            .split_chapters(verse_data, 0.3)
+            .generate()
+            .unwrap();
+        let days = plan.schedule.iter()
+            .filter(|day| day.readings.iter().any(|reading| reading.titles == ["Psalms"] && reading.start_chapter <= 119 && 119 <= reading.end_chapter))
+            .count();
+        assert!(days > 1, "Psalm 119 is read on {} day", days);
+    }
+
+    #[test]
+    fn daily_length_is_reached_when_books_limit_their_days() {
+        // Book balance can't give the gospels a day per share of their length at 4000 characters a day
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        for track in [(40..=66).collect::<Vec<i32>>(), (1..=66).collect()] {
+            for catch_up in [CatchUp::Auto, CatchUp::None] {
+                let plan = PlanRequest::new(start).track(track.clone()).daily_length(4000).catch_up(catch_up).generate().unwrap();
+                let lengths: Vec<f64> = plan.schedule.iter().filter(|day| day.length > 0).map(|day| day.length as f64).collect();
+                let mean = lengths.iter().sum::<f64>() / lengths.len() as f64;
+                assert!((mean - 4000.0).abs() < 200.0, "{} books with {:?} average {}", track.len(), catch_up, mean);
+            }
+        }
+    }
+}
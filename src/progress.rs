@@ -0,0 +1,339 @@
+/*
+    Progress tracking: a generated plan saved to a JSON state file together with a log of
+    the days each reader has completed. For example:
+
+        let mut state = PlanState::new(&plan, "Summer plan");
+        let days = state.parse_days("1-5", today)?;
+        state.mark("Anna", &days, true);
+        let status = state.status("Anna", today);
+        state.save(path)?;
+
+    A reader who has fallen behind can have the rest of the plan spread over the days left:
+
+        let replan = state.replan(5, PlanRequest::new(today).end_date(state.end_date), &bible)?;
+        replan.state.save(path)?;
+*/
+
+use std::collections::{ BTreeMap, BTreeSet };
+use std::fs;
+use std::path::Path;
+use chrono::NaiveDate;
+use serde::{ Deserialize, Serialize };
+use crate::data::BibleData;
+use crate::error::PlannerError;
+use crate::passage::Passage;
+use crate::plan::{ Plan, PlanRequest, Reading };
+
+// The reader name used when none is given
+pub const DEFAULT_READER: &str = "default";
+
+// A saved plan and the days each reader has completed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanState {
+    pub title: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub days: Vec<StateDay>,
+    // The numbers of the days each reader has completed, by reader name
+    pub readers: BTreeMap<String, BTreeSet<i32>>
+}
+
+// One day of a saved plan. A catch-up day has no readings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateDay {
+    // The day's number, counting from 1
+    pub day: i32,
+    pub date: NaiveDate,
+    pub readings: Vec<Reading>,
+    pub length: i32
+}
+
+impl StateDay {
+    pub fn is_catch_up_day(&self) -> bool {
+        self.readings.is_empty()
+    }
+
+    // The number of chapters read on this day. A chapter split across days counts on the day it starts
+    pub fn chapters(&self) -> i32 {
+        self.readings.iter()
+            .map(|r| r.end_chapter - r.start_chapter + if r.start_verse.is_some_and(|verse| verse > 1) { 0 } else { 1 })
+            .sum()
+    }
+}
+
+// A reader's progress through a plan on a given date
+#[derive(Debug, Clone, PartialEq)]
+pub struct Status {
+    // Reading days completed, and the total number of reading days
+    pub days_completed: i32,
+    pub reading_days: i32,
+    pub chapters_read: i32,
+    pub chapters_remaining: i32,
+    // Percentage of the chapters read
+    pub percent: f64,
+    // Reading days completed less the reading days due so far: positive when ahead, negative when behind
+    pub ahead: i32,
+    // Consecutive reading days completed, up to the latest day due. A day due today doesn't break it
+    pub streak: i32,
+    // The first reading day not yet completed
+    pub next_day: Option<i32>
+}
+
+impl PlanState {
+    // Save a generated plan with no readers yet
+    pub fn new(plan: &Plan, title: &str) -> PlanState {
+        let days = plan.readings.iter()
+            .zip(&plan.lengths)
+            .enumerate()
+            .map(|(i, (readings, daily_length))| StateDay {
+                day: i as i32 + 1,
+                date: daily_length.date,
+                readings: readings.clone(),
+                length: daily_length.length
+            })
+            .collect();
+
+        PlanState {
+            title: title.to_string(),
+            start_date: plan.start_date,
+            end_date: plan.end_date,
+            days,
+            readers: BTreeMap::new()
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<PlanState, PlannerError> {
+        let contents = fs::read_to_string(path).map_err(|e| state_error(path, e.to_string()))?;
+        serde_json::from_str(&contents).map_err(|e| state_error(path, e.to_string()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), PlannerError> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| state_error(path, e.to_string()))?;
+        fs::write(path, contents + "\n")?;
+        Ok(())
+    }
+
+    pub fn day(&self, day: i32) -> Option<&StateDay> {
+        self.days.get(usize::try_from(day - 1).ok()?)
+    }
+
+    // Parse a list of days such as "5", "1-7", "2025-06-21", "2025-06-21..2025-06-27" or "today",
+    // separated by `,`, into day numbers
+    pub fn parse_days(&self, selection: &str, today: NaiveDate) -> Result<Vec<i32>, String> {
+        let mut days: Vec<i32> = Vec::new();
+        for item in selection.split(',').map(|item| item.trim()).filter(|item| !item.is_empty()) {
+            let (first, last) = if item.eq_ignore_ascii_case("today") {
+                let day = self.day_on(today)?;
+                (day, day)
+            } else if let Some((first, last)) = item.split_once("..") {
+                (self.parse_day(first)?, self.parse_day(last)?)
+            } else if let Some((first, last)) = item.split_once('-').filter(|_| item.parse::<NaiveDate>().is_err()) {
+                (self.parse_day(first)?, self.parse_day(last)?)
+            } else {
+                let day = self.parse_day(item)?;
+                (day, day)
+            };
+            if last < first {
+                return Err(format!("'{}' ends before it starts", item));
+            }
+            days.extend(first..=last);
+        }
+        Ok(days)
+    }
+
+    // Parse a day number or a date into a day number
+    fn parse_day(&self, text: &str) -> Result<i32, String> {
+        let text = text.trim();
+        match text.parse::<i32>() {
+            Ok(day) if self.day(day).is_some() => Ok(day),
+            Ok(day) => Err(format!("day {} is not in the plan, which has {} days", day, self.days.len())),
+            Err(_) => match text.parse::<NaiveDate>() {
+                Ok(date) => self.day_on(date),
+                Err(_) => Err(format!("invalid day '{}', expected a day number or YYYY-MM-DD", text))
+            }
+        }
+    }
+
+    fn day_on(&self, date: NaiveDate) -> Result<i32, String> {
+        self.days.iter()
+            .find(|day| day.date == date)
+            .map(|day| day.day)
+            .ok_or_else(|| format!("nothing is planned on {}", date))
+    }
+
+    // Record the given days as completed by `reader`, or as not completed if `done` is false
+    pub fn mark(&mut self, reader: &str, days: &[i32], done: bool) {
+        let completed = self.readers.entry(reader.to_string()).or_default();
+        for &day in days {
+            if done {
+                completed.insert(day);
+            } else {
+                completed.remove(&day);
+            }
+        }
+    }
+
+    // The first reading day `reader` has not completed
+    pub fn next_day(&self, reader: &str) -> Option<i32> {
+        let completed = self.readers.get(reader);
+        self.days.iter()
+            .filter(|day| !day.is_catch_up_day())
+            .find(|day| !completed.is_some_and(|completed| completed.contains(&day.day)))
+            .map(|day| day.day)
+    }
+
+    // Find the progress of `reader` as of `today`
+    pub fn status(&self, reader: &str, today: NaiveDate) -> Status {
+        let empty = BTreeSet::new();
+        let completed = self.readers.get(reader).unwrap_or(&empty);
+        let reading_days: Vec<&StateDay> = self.days.iter().filter(|day| !day.is_catch_up_day()).collect();
+        let is_completed = |day: &StateDay| completed.contains(&day.day);
+
+        let chapters: i32 = reading_days.iter().map(|day| day.chapters()).sum();
+        let chapters_read: i32 = reading_days.iter().filter(|day| is_completed(day)).map(|day| day.chapters()).sum();
+        let days_completed = reading_days.iter().filter(|day| is_completed(day)).count() as i32;
+        let days_due = reading_days.iter().filter(|day| day.date <= today).count() as i32;
+
+        // Count back from the latest day due, letting today's reading still be done
+        let mut due = reading_days.iter().rev().skip_while(|day| day.date > today).peekable();
+        if due.peek().is_some_and(|day| day.date == today && !is_completed(day)) {
+            due.next();
+        }
+        let streak = due.take_while(|day| is_completed(day)).count() as i32;
+
+        Status {
+            days_completed,
+            reading_days: reading_days.len() as i32,
+            chapters_read,
+            chapters_remaining: chapters - chapters_read,
+            percent: if chapters > 0 { chapters_read as f64 * 100.0 / chapters as f64 } else { 0.0 },
+            ahead: days_completed - days_due,
+            streak,
+            next_day: self.next_day(reader)
+        }
+    }
+
+    // Plan the readings after day `last` again with `request`, which sets the new dates and options
+    // but no tracks. The chapters left in each track are planned as a track of the new plan, and the
+    // days read so far are kept in the new state. Marks of any later days are cleared
+    pub fn replan(&self, last: i32, request: PlanRequest, bible: &BibleData) -> Result<Replan, PlannerError> {
+        let Some(unread) = usize::try_from(last).ok().and_then(|last| self.days.get(last..)) else {
+            return Err(PlannerError::Replan(format!("day {} is not in the plan, which has {} days", last, self.days.len())));
+        };
+        let segments: Vec<Segment> = get_segments(unread, bible)?;
+        if segments.is_empty() {
+            return Err(PlannerError::Replan(format!("nothing is left to read after day {}", last)));
+        }
+
+        // Make a passage of each segment, with a track for each track that has chapters left
+        let mut track_numbers: Vec<usize> = Vec::new();
+        let mut tracks: Vec<Vec<Passage>> = Vec::new();
+        for segment in &segments {
+            let book = bible.book_by_title(&segment.title)
+                .filter(|book| segment.end_chapter <= book.chapters())
+                .ok_or_else(|| PlannerError::Replan(format!("{} {} is not in the chapter data", segment.title, segment.end_chapter)))?;
+            if track_numbers.last() != Some(&segment.track) {
+                track_numbers.push(segment.track);
+                tracks.push(Vec::new());
+            }
+            tracks.last_mut().unwrap().push(Passage::chapters(book.index, segment.start_chapter, segment.end_chapter));
+        }
+
+        let plan = request
+            .passage_tracks(tracks)
+            .bible_data(bible.clone())
+            .generate()?;
+        let last_date = self.day(last).map(|day| day.date);
+        if last_date.is_some_and(|date| plan.start_date <= date) {
+            return Err(PlannerError::Replan(format!("the new plan must start after day {} on {}", last, last_date.unwrap())));
+        }
+
+        // Append the new days to the days read so far, with the original track numbers
+        let mut state = self.clone();
+        state.days.truncate(last as usize);
+        state.days.extend(PlanState::new(&plan, &self.title).days.into_iter().map(|mut day| {
+            day.day += last;
+            for reading in day.readings.iter_mut() {
+                reading.track = track_numbers[reading.track - 1];
+            }
+            day
+        }));
+        state.end_date = plan.end_date;
+        for completed in state.readers.values_mut() {
+            completed.retain(|&day| day <= last);
+        }
+
+        let original_length = average_length(unread);
+        let new_length = average_length(&state.days[last as usize..]);
+        Ok(Replan {
+            plan,
+            state,
+            chapters: unread.iter().map(|day| day.chapters()).sum(),
+            original_length,
+            new_length
+        })
+    }
+}
+
+// The unread readings of a saved plan, planned again
+#[derive(Debug, Clone)]
+pub struct Replan {
+    // The new plan of the unread readings, with its days numbered from 1 and its tracks numbered
+    // in order among the tracks with chapters left
+    pub plan: Plan,
+    // The saved plan with the days read so far followed by the new days
+    pub state: PlanState,
+    // The number of chapters planned again
+    pub chapters: i32,
+    // The average length of a reading day left in the original plan, and in the new plan
+    pub original_length: i32,
+    pub new_length: i32
+}
+
+// A run of consecutive chapters of one book left to read in a track
+struct Segment {
+    track: usize,
+    title: String,
+    start_chapter: i32,
+    end_chapter: i32
+}
+
+// Split the readings of the given days into runs of consecutive chapters of a book, ordered by track.
+// A reading of several short books reads each of them in full. A reading that continues a split
+// chapter extends the run that reads the start of the chapter, or else starts at the whole chapter
+fn get_segments(days: &[StateDay], bible: &BibleData) -> Result<Vec<Segment>, PlannerError> {
+    let mut segments: Vec<Segment> = Vec::new();
+    for reading in days.iter().flat_map(|day| &day.readings) {
+        let continues_chapter = reading.start_verse.is_some_and(|verse| verse > 1);
+        let ranges: Vec<(String, i32, i32)> = match reading.titles.as_slice() {
+            [title] => vec![(title.clone(), reading.start_chapter, reading.end_chapter)],
+            titles => titles.iter()
+                .map(|title| bible.book_by_title(title)
+                    .map(|book| (title.clone(), 1, book.chapters()))
+                    .ok_or_else(|| PlannerError::Replan(format!("{} is not in the chapter data", title))))
+                .collect::<Result<_, _>>()?
+        };
+
+        for (title, start_chapter, end_chapter) in ranges {
+            match segments.iter_mut().rev().find(|segment| segment.track == reading.track) {
+                Some(segment) if segment.title == title && segment.end_chapter + 1 == start_chapter =>
+                    segment.end_chapter = end_chapter,
+                Some(segment) if segment.title == title && segment.end_chapter == start_chapter && continues_chapter =>
+                    segment.end_chapter = end_chapter,
+                _ => segments.push(Segment { track: reading.track, title, start_chapter, end_chapter })
+            }
+        }
+    }
+    segments.sort_by_key(|segment| segment.track);
+    Ok(segments)
+}
+
+// The average length of the reading days among `days`, leaving out catch-up days
+fn average_length(days: &[StateDay]) -> i32 {
+    let lengths: Vec<i32> = days.iter().filter(|day| !day.is_catch_up_day()).map(|day| day.length).collect();
+    if lengths.is_empty() { 0 } else { lengths.iter().sum::<i32>() / lengths.len() as i32 }
+}
+
+fn state_error(path: &Path, message: String) -> PlannerError {
+    PlannerError::State { path: path.to_path_buf(), message }
+}
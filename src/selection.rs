@@ -0,0 +1,201 @@
+/*
+    Book selection syntax, parsed into the passages of one or more tracks.
+
+    A selection is a list of tracks separated by `;`. Each track is a list of
+    items separated by `,`, and each item is one of:
+        - a book name or abbreviation:  "Genesis", "Gen", "1 Cor"
+        - a book index:                 "19"
+        - a range of books:             "Matthew-John", "40-66"
+        - a named group:                "OT", "NT", "Gospels", "Pauline Epistles"
+        - the chronological order:      "Chronological", every chapter in the order of events
+    Any item may be followed by a repeat count, e.g. "Psalms x2" or "Psalms-Proverbs x2".
+
+    For example, "NT; Psalms-Proverbs x2" reads through the New Testament once and
+    Psalms & Proverbs twice.
+*/
+
+use crate::error::PlannerError;
+use crate::passage::{ Passage, chronological_order };
+
+// Named groups of books and the index range each covers
+const GROUPS: [(&str, i32, i32); 19] = [
+    ("Bible", 1, 66),
+    ("Whole Bible", 1, 66),
+    ("OT", 1, 39),
+    ("Old Testament", 1, 39),
+    ("NT", 40, 66),
+    ("New Testament", 40, 66),
+    ("Law", 1, 5),
+    ("Pentateuch", 1, 5),
+    ("History", 6, 17),
+    ("Wisdom", 18, 22),
+    ("Poetry", 18, 22),
+    ("Prophets", 23, 39),
+    ("Major Prophets", 23, 27),
+    ("Minor Prophets", 28, 39),
+    ("Gospels", 40, 43),
+    ("Epistles", 45, 65),
+    ("Pauline Epistles", 45, 57),
+    ("General Epistles", 58, 65),
+    ("Apocalypse", 66, 66)
+];
+
+// The name of the built-in chronological order
+const CHRONOLOGICAL: &str = "Chronological";
+
+// Common abbreviations that are not a unique prefix of the book title
+const ABBREVIATIONS: [(&str, i32); 12] = [
+    ("Gn", 1),
+    ("Dt", 5),
+    ("Jdg", 7),
+    ("Song of Songs", 22),
+    ("Mt", 40),
+    ("Mk", 41),
+    ("Lk", 42),
+    ("Jn", 43),
+    ("Phil", 50),
+    ("Phm", 57),
+    ("Jas", 59),
+    ("Revelations", 66)
+];
+
+// Parse a selection into the passages of each track, whole books except in the chronological order.
+// `titles` holds the book titles in index order, so the title of book index `i` is `titles[i - 1]`
+pub fn parse_selection(selection: &str, titles: &[String]) -> Result<Vec<Vec<Passage>>, PlannerError> {
+    let mut tracks: Vec<Vec<Passage>> = Vec::new();
+
+    for track in selection.split(';').map(str::trim).filter(|t| !t.is_empty()) {
+        let mut passages: Vec<Passage> = Vec::new();
+        for item in track.split(',').map(str::trim) {
+            if item.is_empty() {
+                return Err(PlannerError::InvalidSelection(format!("empty book selection in track '{}'", track)));
+            }
+            passages.extend(parse_item(item, titles)?);
+        }
+        tracks.push(passages);
+    }
+
+    if tracks.is_empty() {
+        return Err(PlannerError::NoBooksSelected);
+    }
+    Ok(tracks)
+}
+
+// Parse a single item, with an optional repeat count, into its passages
+fn parse_item(item: &str, titles: &[String]) -> Result<Vec<Passage>, PlannerError> {
+    let (books, repeat) = split_repeat(item)?;
+    if normalize(books) == normalize(CHRONOLOGICAL) {
+        return Ok(chronological_order()?.repeat(repeat));
+    }
+
+    let indexes: Vec<i32> = if let Some((first, last)) = find_group(books) {
+        (first..=last).collect()
+    } else if let Some((first_name, last_name)) = books.split_once('-') {
+        // A group at either end stands for its first or last book, to point to the books it covers
+        let (first_group, last_group) = (find_group(first_name), find_group(last_name));
+        let first = first_group.map_or_else(|| find_book(first_name, titles), |(first, _)| Ok(first))?;
+        let last = last_group.map_or_else(|| find_book(last_name, titles), |(_, last)| Ok(last))?;
+        if first > last {
+            return Err(PlannerError::InvalidSelection(format!("range '{}' is reversed ({} comes after {})",
+                books, titles[first as usize - 1], titles[last as usize - 1])));
+        }
+        // A range runs between books, so suggest the books the groups cover, and a group covering exactly those
+        if first_group.is_some() || last_group.is_some() {
+            let mut suggestions: Vec<String> = vec![format!("'{}-{}'", titles[first as usize - 1], titles[last as usize - 1])];
+            if let Some((group, _, _)) = GROUPS.iter().find(|&&(_, group_first, group_last)| (group_first, group_last) == (first, last)) {
+                suggestions.push(format!("'{}'", group));
+            }
+            return Err(PlannerError::InvalidSelection(format!("range '{}' must run between books rather than groups, did you mean {}?",
+                books, suggestions.join(" or "))));
+        }
+        (first..=last).collect()
+    } else {
+        vec![find_book(books, titles)?]
+    };
+
+    Ok(indexes.into_iter().map(Passage::book).collect::<Vec<Passage>>().repeat(repeat))
+}
+
+// Split a trailing repeat count such as "x2" from an item
+fn split_repeat(item: &str) -> Result<(&str, usize), PlannerError> {
+    let Some(position) = item.rfind(['x', 'X']) else {
+        return Ok((item, 1));
+    };
+    let (books, count) = (item[..position].trim(), &item[position + 1..]);
+
+    // Only treat the suffix as a repeat count when it is numeric, so titles containing 'x' still match
+    if books.is_empty() || count.is_empty() || !count.trim().chars().all(|c| c.is_ascii_digit()) {
+        return Ok((item, 1));
+    }
+    match count.trim().parse::<usize>() {
+        Ok(0) | Err(_) => Err(PlannerError::InvalidSelection(format!("invalid repeat count in '{}'", item))),
+        Ok(count) => Ok((books, count))
+    }
+}
+
+// Find a named group of books
+fn find_group(name: &str) -> Option<(i32, i32)> {
+    let key = normalize(name);
+    GROUPS.iter()
+        .find(|(group, _, _)| normalize(group) == key)
+        .map(|&(_, first, last)| (first, last))
+}
+
+// Find the index of a book by index, title, abbreviation or unique title prefix
+fn find_book(name: &str, titles: &[String]) -> Result<i32, PlannerError> {
+    let name = name.trim();
+    if let Ok(index) = name.parse::<i32>() {
+        if index < 1 || index as usize > titles.len() {
+            return Err(PlannerError::InvalidSelection(format!("book index {} is outside 1-{}", index, titles.len())));
+        }
+        return Ok(index);
+    }
+
+    let key = normalize(name);
+    if key.is_empty() {
+        return Err(PlannerError::InvalidSelection(format!("missing book name in '{}'", name)));
+    }
+    if let Some(position) = titles.iter().position(|title| normalize(title) == key) {
+        return Ok(position as i32 + 1);
+    }
+    if let Some(&(_, index)) = ABBREVIATIONS.iter().find(|(abbreviation, _)| normalize(abbreviation) == key) {
+        return Ok(index);
+    }
+
+    let matches: Vec<usize> = titles.iter()
+        .enumerate()
+        .filter(|(_, title)| normalize(title).starts_with(&key))
+        .map(|(i, _)| i)
+        .collect();
+    match matches.len() {
+        1 => Ok(matches[0] as i32 + 1),
+        0 => Err(PlannerError::UnknownBook { name: name.to_string(), suggestions: suggest_books(name, titles) }),
+        _ => Err(PlannerError::AmbiguousBook {
+            name: name.to_string(),
+            matches: matches.iter().map(|&i| titles[i].clone()).collect()
+        })
+    }
+}
+
+// Find the closest titles and groups to an unknown book name
+fn suggest_books(name: &str, titles: &[String]) -> Vec<String> {
+    let key = normalize(name);
+    let mut candidates: Vec<(f64, &str)> = titles.iter()
+        .map(String::as_str)
+        .chain(GROUPS.iter().map(|(group, _, _)| *group))
+        .chain([CHRONOLOGICAL])
+        .map(|candidate| (strsim::normalized_levenshtein(&key, &normalize(candidate)), candidate))
+        .filter(|(score, _)| *score >= 0.5)
+        .collect();
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    candidates.iter().take(3).map(|(_, candidate)| candidate.to_string()).collect()
+}
+
+// Lowercase and remove spaces and periods so "1 Cor." matches "1corinthians"
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && *c != '.')
+        .flat_map(char::to_lowercase)
+        .collect()
+}